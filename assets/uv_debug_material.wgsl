@@ -1,6 +1,18 @@
+struct UvDebugMaterial {
+    tint: vec4<f32>,
+    grid_scale: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: UvDebugMaterial;
+
 @fragment
 fn fragment(
     #import bevy_pbr::mesh_vertex_output
 ) -> @location(0) vec4<f32> {
-    return vec4(uv.x, uv.y, 0.0, 0.0);
+    // Each grid cell runs red and green from 0 to 1, with a dark line along its edges
+    let cell = fract(uv * material.grid_scale);
+    let edge = step(cell, vec2(0.05));
+    let line = 1.0 - max(edge.x, edge.y) * 0.8;
+    return vec4(vec3(cell, 0.0) * line, 1.0) * material.tint;
 }
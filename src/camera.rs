@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Where an orbit camera looks from, kept between sessions so a road doesn't have to be framed
+/// again after every restart
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraView {
+    /// Point the camera orbits around
+    pub focus: Vec3,
+    /// Distance from the focus
+    pub radius: f32,
+    /// Rotation about the global Y axis, in radians
+    pub alpha: f32,
+    /// Rotation about the camera's local X axis, in radians
+    pub beta: f32,
+}
+
+impl CameraView {
+    /// Writes the view to `path` as one `name value...` line per field
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let Vec3 { x, y, z } = self.focus;
+        fs::write(
+            path,
+            format!(
+                "focus {x} {y} {z}\nradius {}\nalpha {}\nbeta {}\n",
+                self.radius, self.alpha, self.beta
+            ),
+        )
+    }
+
+    /// Reads a view written by [`CameraView::save`]. Fields can come in any order, but every one
+    /// of them has to be there
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let contents = fs::read_to_string(path)?;
+        let (mut focus, mut radius, mut alpha, mut beta) = (None, None, None, None);
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let mut words = line.split_whitespace();
+            let name = words.next().unwrap_or_default();
+            let values = words
+                .map(str::parse)
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|_| invalid("camera view values must be numbers"))?;
+            match (name, values.as_slice()) {
+                ("focus", &[x, y, z]) => focus = Some(Vec3::new(x, y, z)),
+                ("radius", &[value]) => radius = Some(value),
+                ("alpha", &[value]) => alpha = Some(value),
+                ("beta", &[value]) => beta = Some(value),
+                _ => return Err(invalid(&format!("unexpected camera view line '{line}'"))),
+            }
+        }
+        match (focus, radius, alpha, beta) {
+            (Some(focus), Some(radius), Some(alpha), Some(beta)) => Ok(CameraView {
+                focus,
+                radius,
+                alpha,
+                beta,
+            }),
+            _ => Err(invalid("camera view is missing a field")),
+        }
+    }
+}
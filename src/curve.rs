@@ -0,0 +1,885 @@
+use bevy::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+
+/// Point on the cubic Bézier defined by `a`, `b`, `c`, `d` at parameter `t`
+pub fn cubic_bezier(a: Vec3, b: Vec3, c: Vec3, d: Vec3, t: f32) -> Vec3 {
+    let ab = a.lerp(b, t);
+    let bc = b.lerp(c, t);
+    let cd = c.lerp(d, t);
+    let abbc = ab.lerp(bc, t);
+    let bccd = bc.lerp(cd, t);
+    abbc.lerp(bccd, t)
+}
+
+/// First derivative of the curve with respect to `t`. Not normalized, so its length is the speed
+/// of the curve at `t`
+pub fn cubic_bezier_tangent(a: Vec3, b: Vec3, c: Vec3, d: Vec3, t: f32) -> Vec3 {
+    let mt = 1.0 - t;
+    3.0 * mt * mt * (b - a) + 6.0 * mt * t * (c - b) + 3.0 * t * t * (d - c)
+}
+
+/// Slowest the curve can travel at `t` and still have a trustworthy direction
+const MIN_SPEED: f32 = 1e-5;
+
+/// Unit direction the curve travels in at `t`. The derivative vanishes where control points sit on
+/// top of each other, such as a handle dragged onto its anchor, so the chord from `a` to `d` stands
+/// in there. A segment collapsed to a single point falls back to X
+pub fn cubic_bezier_direction(a: Vec3, b: Vec3, c: Vec3, d: Vec3, t: f32) -> Vec3 {
+    let tangent = cubic_bezier_tangent(a, b, c, d, t);
+    if tangent.length() > MIN_SPEED {
+        return tangent.normalize();
+    }
+    (d - a).try_normalize().unwrap_or(Vec3::X)
+}
+
+/// Second derivative of the curve with respect to `t`
+pub fn cubic_bezier_second_derivative(a: Vec3, b: Vec3, c: Vec3, d: Vec3, t: f32) -> Vec3 {
+    6.0 * (1.0 - t) * (c - 2.0 * b + a) + 6.0 * t * (d - 2.0 * c + b)
+}
+
+/// Signed curvature about the world Y axis: positive when the curve turns left (counter-clockwise
+/// seen from above), negative when it turns right
+pub fn cubic_bezier_curvature(a: Vec3, b: Vec3, c: Vec3, d: Vec3, t: f32) -> f32 {
+    let first = cubic_bezier_tangent(a, b, c, d, t);
+    let second = cubic_bezier_second_derivative(a, b, c, d, t);
+    let speed = first.length();
+    if speed < f32::EPSILON {
+        return 0.0;
+    }
+    first.cross(second).dot(Vec3::Y) / speed.powi(3)
+}
+
+/// Curvature at `t` as a vector pointing towards the centre of the bend, one over the bend's radius
+/// long. Zero where the curve runs straight
+pub fn cubic_bezier_curvature_vector(a: Vec3, b: Vec3, c: Vec3, d: Vec3, t: f32) -> Vec3 {
+    let first = cubic_bezier_tangent(a, b, c, d, t);
+    let second = cubic_bezier_second_derivative(a, b, c, d, t);
+    let speed_squared = first.length_squared();
+    if speed_squared < f32::EPSILON {
+        return Vec3::ZERO;
+    }
+    // The part of the acceleration across the curve, over the speed squared
+    first.cross(second).cross(first) / (speed_squared * speed_squared)
+}
+
+/// Point on the quadratic Bézier defined by `a`, `b`, `c` at parameter `t`
+pub fn quadratic_bezier(a: Vec3, b: Vec3, c: Vec3, t: f32) -> Vec3 {
+    a.lerp(b, t).lerp(b.lerp(c, t), t)
+}
+
+/// First derivative of the quadratic with respect to `t`
+pub fn quadratic_bezier_tangent(a: Vec3, b: Vec3, c: Vec3, t: f32) -> Vec3 {
+    2.0 * (1.0 - t) * (b - a) + 2.0 * t * (c - b)
+}
+
+/// Cubic tracing exactly the same curve as the quadratic `a`, `b`, `c`, with the same derivative at
+/// every `t`. Anything built on the cubic, frames included, follows the quadratic unchanged
+pub fn elevate_quadratic(a: Vec3, b: Vec3, c: Vec3) -> [Vec3; 4] {
+    [a, a + 2.0 / 3.0 * (b - a), c + 2.0 / 3.0 * (b - c), c]
+}
+
+/// Point on the curve at `t` and the rotation of the curve's frame there. Local Z points back along
+/// the curve and local X lies across it, perpendicular to `up`, so profiles authored in the XY
+/// plane extrude along the curve. Local Y is as close to `up` as it can be while staying
+/// perpendicular to the curve. Where the curve runs parallel to `up` there's no sideways direction
+/// to take from it, so a secondary axis perpendicular to `up` stands in to keep the basis from
+/// collapsing. The basis is orthonormalized, so it's always a proper rotation
+pub fn cubic_bezier_quat(a: Vec3, b: Vec3, c: Vec3, d: Vec3, t: f32, up: Vec3) -> (Vec3, Quat) {
+    let position = cubic_bezier(a, b, c, d, t);
+    let direction = cubic_bezier_direction(a, b, c, d, t);
+    (position, upright_rotation(direction, up))
+}
+
+/// Rotation of a frame facing along `direction` (a unit vector), kept as upright as it can be
+/// against `up`
+fn upright_rotation(direction: Vec3, up: Vec3) -> Quat {
+    let z = -direction;
+    let up = up.try_normalize().unwrap_or(Vec3::Y);
+    let mut x = up.cross(z);
+    if x.length_squared() < 1e-6 {
+        x = up.any_orthonormal_vector().cross(z);
+    }
+    let x = x.normalize();
+    let y = z.cross(x);
+    Quat::from_mat3(&Mat3::from_cols(x, y, z))
+}
+
+/// Frame of the curve at `t`, translated to the point on the curve, as a matrix. See
+/// [`cubic_bezier_quat`]
+pub fn cubic_bezier_matrix(a: Vec3, b: Vec3, c: Vec3, d: Vec3, t: f32, up: Vec3) -> Mat4 {
+    let (position, rotation) = cubic_bezier_quat(a, b, c, d, t, up);
+    Mat4::from_rotation_translation(rotation, position)
+}
+
+/// How each slice's frame is oriented about the curve
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, Default, PartialEq)]
+pub enum FrameMode {
+    /// Keep the cross-section as upright as it can be against the up direction. Roads stay level,
+    /// but the frame flips over where the curve passes straight up or down through it
+    #[default]
+    WorldUp,
+    /// Start upright against the up direction, then carry the frame along the curve turning as
+    /// little as possible. Never twists or flips, but drifts away from upright as the curve winds,
+    /// and the ends of a closed loop don't generally line up
+    ParallelTransport,
+    /// Keep the cross-section as upright as it can be against this direction instead, such as the
+    /// normal of a wall that a pipe runs up. Flips where the curve runs along it, like `WorldUp`
+    FixedUp(Vec3),
+}
+
+/// Point on the curve and rotation of its frame at each of `samples`, a segment and `t` along it,
+/// oriented by `mode` against `up`. Parallel transport carries the frame from one sample to the
+/// next, so the samples need to be in order along the curve and close enough together to follow it
+pub fn curve_frames(samples: &[([Vec3; 4], f32)], mode: FrameMode, up: Vec3) -> Vec<(Vec3, Quat)> {
+    let up = match mode {
+        FrameMode::FixedUp(up) => up,
+        FrameMode::WorldUp | FrameMode::ParallelTransport => up,
+    };
+    let frame = |&([a, b, c, d], t): &([Vec3; 4], f32)| cubic_bezier_quat(a, b, c, d, t, up);
+    match mode {
+        FrameMode::ParallelTransport => samples
+            .iter()
+            .scan(None, |previous, sample| {
+                let ([a, b, c, d], t) = *sample;
+                let next = match *previous {
+                    None => frame(sample),
+                    Some((from, rotation)) => {
+                        let to = cubic_bezier(a, b, c, d, t);
+                        let direction = cubic_bezier_direction(a, b, c, d, t);
+                        (to, transport_rotation(rotation, from, to, direction))
+                    }
+                };
+                *previous = Some(next);
+                Some(next)
+            })
+            .collect(),
+        FrameMode::WorldUp | FrameMode::FixedUp(_) => samples.iter().map(frame).collect(),
+    }
+}
+
+/// Rotation of the frame at each of `points`, a polyline followed exactly rather than a curve,
+/// oriented by `mode` against `up` like [`curve_frames`]. Each point faces along its own segment,
+/// and the points where the polyline turns face halfway between the segments either side, so the
+/// cross-sections meet at a mitre. Repeated points take the direction of the nearest segment that
+/// goes somewhere
+pub fn polyline_frames(points: &[Vec3], mode: FrameMode, up: Vec3) -> Vec<Quat> {
+    let up = match mode {
+        FrameMode::FixedUp(up) => up,
+        FrameMode::WorldUp | FrameMode::ParallelTransport => up,
+    };
+    let segment_direction = |i: usize| (points[i + 1] - points[i]).try_normalize();
+    let directions = (0..points.len())
+        .map(|i| {
+            let incoming = (0..i).rev().find_map(segment_direction);
+            let outgoing = (i..points.len().saturating_sub(1)).find_map(segment_direction);
+            match (incoming, outgoing) {
+                (Some(incoming), Some(outgoing)) => {
+                    (incoming + outgoing).try_normalize().unwrap_or(outgoing)
+                }
+                (direction, None) | (None, direction) => direction.unwrap_or(Vec3::Z),
+            }
+        })
+        .collect::<Vec<_>>();
+    match mode {
+        FrameMode::ParallelTransport => {
+            let mut rotations = Vec::with_capacity(points.len());
+            for (i, &direction) in directions.iter().enumerate() {
+                rotations.push(match rotations.last() {
+                    None => upright_rotation(direction, up),
+                    Some(&rotation) => {
+                        transport_rotation(rotation, points[i - 1], points[i], direction)
+                    }
+                });
+            }
+            rotations
+        }
+        FrameMode::WorldUp | FrameMode::FixedUp(_) => directions
+            .into_iter()
+            .map(|direction| upright_rotation(direction, up))
+            .collect(),
+    }
+}
+
+/// Frame `rotation` at `from`, carried along to `to` where the curve travels in `direction`, turning
+/// as little as possible on the way. This is one step of the double reflection method: reflecting
+/// the frame through the plane bisecting the chord, then through the one taking the reflected
+/// tangent onto the new tangent. Stepped along a curve, it gives rotation-minimizing frames that
+/// don't twist about the tangent no matter how the curve winds, at the cost of no longer keeping
+/// any particular direction up
+pub fn transport_rotation(rotation: Quat, from: Vec3, to: Vec3, direction: Vec3) -> Quat {
+    let reflect = |v: Vec3, normal: Vec3| {
+        let length_squared = normal.length_squared();
+        if length_squared < 1e-12 {
+            v
+        } else {
+            v - 2.0 * normal.dot(v) / length_squared * normal
+        }
+    };
+    let (y, z) = (rotation * Vec3::Y, rotation * Vec3::Z);
+    let chord = to - from;
+    let (reflected_y, reflected_z) = (reflect(y, chord), reflect(z, chord));
+    let z = -direction;
+    let y = reflect(reflected_y, z - reflected_z);
+    // Reorthonormalize, so rounding doesn't build up from one step to the next
+    let Some(x) = y.cross(z).try_normalize() else {
+        return rotation;
+    };
+    Quat::from_mat3(&Mat3::from_cols(x, z.cross(x), z))
+}
+
+/// Parameters to place slices at along a cubic, spaced so each span deviates from the curve by
+/// roughly `tolerance`. A span of length `h` on a curve of curvature `k` sags by about `k h² / 8`,
+/// so slices are distributed by `√k` along the arc: densely through tight bends, sparsely on
+/// straights. The slice count is clamped to `min_slices..=max_slices` (and at least 2, for the two
+/// ends)
+pub fn adaptive_parameters(
+    a: Vec3,
+    b: Vec3,
+    c: Vec3,
+    d: Vec3,
+    tolerance: f32,
+    min_slices: usize,
+    max_slices: usize,
+) -> Vec<f32> {
+    let min_slices = min_slices.max(2);
+    let max_slices = max_slices.max(min_slices);
+
+    // Cumulative √k weighted arc length, sampled densely
+    let mut weights = vec![0.0];
+    let mut previous_point = a;
+    for j in 1..=ARC_LENGTH_SAMPLES {
+        let t = j as f32 / ARC_LENGTH_SAMPLES as f32;
+        let point = cubic_bezier(a, b, c, d, t);
+        let first = cubic_bezier_tangent(a, b, c, d, t);
+        let second = cubic_bezier_second_derivative(a, b, c, d, t);
+        let speed = first.length();
+        let curvature = if speed < f32::EPSILON {
+            0.0
+        } else {
+            first.cross(second).length() / speed.powi(3)
+        };
+        let weight = weights[j - 1] + curvature.sqrt() * point.distance(previous_point);
+        weights.push(weight);
+        previous_point = point;
+    }
+    let total_weight = weights[ARC_LENGTH_SAMPLES];
+
+    let spans = (total_weight / (8.0 * tolerance.max(f32::EPSILON)).sqrt()).ceil() as usize;
+    let slices = (spans + 1).clamp(min_slices, max_slices);
+    if total_weight <= f32::EPSILON {
+        // Straight, nothing to concentrate slices around
+        return (0..slices)
+            .map(|i| i as f32 / (slices - 1) as f32)
+            .collect();
+    }
+
+    (0..slices)
+        .map(|i| {
+            // Weight plateaus on straight stretches, so pin the ends rather than searching for them
+            if i == slices - 1 {
+                return 1.0;
+            }
+            let target = total_weight * i as f32 / (slices - 1) as f32;
+            let j = weights
+                .partition_point(|weight| *weight < target)
+                .clamp(1, ARC_LENGTH_SAMPLES);
+            let (w0, w1) = (weights[j - 1], weights[j]);
+            let fraction = if w1 > w0 {
+                (target - w0) / (w1 - w0)
+            } else {
+                0.0
+            };
+            (j as f32 - 1.0 + fraction) / ARC_LENGTH_SAMPLES as f32
+        })
+        .collect()
+}
+
+/// Parameters to place slices at along a cubic, found by halving it with [`split_cubic`] until
+/// every piece lies within `max_chord_error` of its chord, always halving the piece that strays
+/// furthest first. A piece's error is how far the curve strays from the chord at its quarter, half
+/// and three quarter points, so an S bend crossing its chord at the midpoint still gets split. This
+/// is measured in world units, so it means the same at any scale of road. Stops at `max_slices`
+/// slices however far the pieces still stray, so a near cusp can't subdivide forever. When fewer
+/// than `min_slices` slices are needed, such as with a very large error, they're spaced evenly
+/// instead
+pub fn subdivision_parameters(
+    a: Vec3,
+    b: Vec3,
+    c: Vec3,
+    d: Vec3,
+    max_chord_error: f32,
+    min_slices: usize,
+    max_slices: usize,
+) -> Vec<f32> {
+    let min_slices = min_slices.max(2);
+    let max_slices = max_slices.max(min_slices);
+    let chord_error = |[a, b, c, d]: [Vec3; 4]| {
+        [0.25, 0.5, 0.75]
+            .into_iter()
+            .map(|t| {
+                let point = cubic_bezier(a, b, c, d, t);
+                let chord = d - a;
+                let along = (point - a).dot(chord) / chord.length_squared().max(f32::EPSILON);
+                point.distance(a + chord * along.clamp(0.0, 1.0))
+            })
+            .fold(0.0, f32::max)
+    };
+    // Start and end `t` of each piece, its control points and how far it strays
+    let mut pieces = vec![(0.0, 1.0, [a, b, c, d], chord_error([a, b, c, d]))];
+    while pieces.len() + 1 < max_slices {
+        let (worst, &(_, _, _, error)) = pieces
+            .iter()
+            .enumerate()
+            .max_by(|(_, x), (_, y)| x.3.total_cmp(&y.3))
+            .unwrap();
+        if error <= max_chord_error {
+            break;
+        }
+        let (start, end, [a, b, c, d], _) = pieces[worst];
+        let (left, right) = split_cubic(a, b, c, d, 0.5);
+        let middle = (start + end) / 2.0;
+        pieces[worst] = (start, middle, left, chord_error(left));
+        pieces.insert(worst + 1, (middle, end, right, chord_error(right)));
+    }
+    if pieces.len() + 1 < min_slices {
+        return (0..min_slices)
+            .map(|i| i as f32 / (min_slices - 1) as f32)
+            .collect();
+    }
+    pieces
+        .iter()
+        .map(|&(start, ..)| start)
+        .chain([1.0])
+        .collect()
+}
+
+/// Splits a cubic at `t` with De Casteljau subdivision. The two halves trace exactly the same curve
+/// as the original over `0..=t` and `t..=1`
+pub fn split_cubic(a: Vec3, b: Vec3, c: Vec3, d: Vec3, t: f32) -> ([Vec3; 4], [Vec3; 4]) {
+    let ab = a.lerp(b, t);
+    let bc = b.lerp(c, t);
+    let cd = c.lerp(d, t);
+    let abbc = ab.lerp(bc, t);
+    let bccd = bc.lerp(cd, t);
+    let split_point = abbc.lerp(bccd, t);
+    ([a, ab, abbc, split_point], [split_point, bccd, cd, d])
+}
+
+/// Number of samples per segment used to approximate arc length
+const ARC_LENGTH_SAMPLES: usize = 64;
+
+/// Splits a chain of control points into cubic segments. Consecutive segments share their end
+/// point, so `n` segments take `3n + 1` points. Trailing points that don't complete a segment are
+/// ignored
+pub fn segments(control_points: &[Vec3]) -> impl Iterator<Item = [Vec3; 4]> + '_ {
+    control_points
+        .windows(4)
+        .step_by(3)
+        .map(|window| [window[0], window[1], window[2], window[3]])
+}
+
+/// Whether `control_points` make up a whole number of cubic segments (`3n + 1` points, for at least
+/// one segment) with none left over
+pub fn is_cubic_chain(control_points: &[Vec3]) -> bool {
+    control_points.len() >= 4 && (control_points.len() - 1) % 3 == 0
+}
+
+/// Splits a chain of control points into quadratic segments. Consecutive segments share their end
+/// point, so `n` segments take `2n + 1` points. A trailing point that doesn't complete a segment is
+/// ignored
+pub fn quadratic_segments(control_points: &[Vec3]) -> impl Iterator<Item = [Vec3; 3]> + '_ {
+    control_points
+        .windows(3)
+        .step_by(2)
+        .map(|window| [window[0], window[1], window[2]])
+}
+
+/// Chain of cubic segments tracing the same curve as a chain of quadratic segments, so it can be
+/// sampled and extruded like any other
+pub fn quadratic_chain(control_points: &[Vec3]) -> Vec<Vec3> {
+    let mut chain = control_points
+        .first()
+        .copied()
+        .into_iter()
+        .collect::<Vec<_>>();
+    for [a, b, c] in quadratic_segments(control_points) {
+        let [_, b, c, d] = elevate_quadratic(a, b, c);
+        chain.extend([b, c, d]);
+    }
+    chain
+}
+
+/// Quadratic control points with a segment appended that joins the last point back to the first.
+/// The closing handle mirrors the last handle, so the loop stays smooth through the last point. One
+/// handle can't line up with both ends, so the join at the first point may have a corner
+pub fn closed_quadratic_chain(control_points: &[Vec3]) -> Vec<Vec3> {
+    let mut closed = control_points.to_vec();
+    if let [first, .., second_last, last] = *control_points {
+        closed.extend([2.0 * last - second_last, first]);
+    }
+    closed
+}
+
+/// Splits quadratic segment `segment` of the chain at `t`, inserting a new anchor and its handles.
+/// The curve keeps exactly the same shape
+pub fn insert_quadratic_anchor(control_points: &mut Vec<Vec3>, segment: usize, t: f32) {
+    let start = segment * 2;
+    let Some(&[a, b, c]) = control_points.get(start..start + 3) else {
+        return;
+    };
+    let (ab, bc) = (a.lerp(b, t), b.lerp(c, t));
+    control_points.splice(start + 1..start + 2, [ab, ab.lerp(bc, t), bc]);
+}
+
+/// Splits segment `segment` of the chain at `t`, inserting a new anchor and its handles. The curve
+/// keeps exactly the same shape
+pub fn insert_anchor(control_points: &mut Vec<Vec3>, segment: usize, t: f32) {
+    let start = segment * 3;
+    let Some(&[a, b, c, d]) = control_points.get(start..start + 4) else {
+        return;
+    };
+    let (left, right) = split_cubic(a, b, c, d, t);
+    control_points.splice(
+        start + 1..start + 3,
+        [left[1], left[2], left[3], right[1], right[2]],
+    );
+}
+
+/// Segment index and `t` of the sampled point on the chain closest to a ray, along with its
+/// distance from the ray. Accurate enough for picking, not for exact projection
+pub fn nearest_to_ray(
+    control_points: &[Vec3],
+    origin: Vec3,
+    direction: Vec3,
+) -> Option<(usize, f32, f32)> {
+    let direction = direction.normalize();
+    segments(control_points)
+        .enumerate()
+        .flat_map(|(i, [a, b, c, d])| {
+            (0..=ARC_LENGTH_SAMPLES).map(move |j| {
+                let t = j as f32 / ARC_LENGTH_SAMPLES as f32;
+                let point = cubic_bezier(a, b, c, d, t);
+                let along_ray = (point - origin).dot(direction).max(0.0);
+                (i, t, point.distance(origin + direction * along_ray))
+            })
+        })
+        .min_by(|(.., x), (.., y)| x.total_cmp(y))
+}
+
+/// Control points for a new segment continuing straight on from the end of the chain, spaced by
+/// the length of the last handle so it carries on at the same pace. `None` if there's no segment to
+/// extend from
+pub fn extrapolate_segment(control_points: &[Vec3]) -> Option<[Vec3; 3]> {
+    let [.., handle, end] = *control_points else {
+        return None;
+    };
+    let step = if end.distance_squared(handle) > f32::EPSILON {
+        end - handle
+    } else {
+        Vec3::X
+    };
+    Some([end + step, end + 2.0 * step, end + 3.0 * step])
+}
+
+/// Control points with a segment appended that joins the last point back to the first. The closing
+/// segment's handles mirror the handles either side of the join, so the loop stays smooth through
+/// both end points
+pub fn closed_chain(control_points: &[Vec3]) -> Vec<Vec3> {
+    let mut closed = control_points.to_vec();
+    if let [first, second, .., second_last, last] = *control_points {
+        closed.extend([2.0 * last - second_last, 2.0 * first - second, first]);
+    }
+    closed
+}
+
+/// Joins the start of the `to` chain onto the end of the `from` chain with C1 continuity. The first
+/// anchor of `to` moves onto the last anchor of `from`, and the handles either side of the join are
+/// lined up along the direction `from` arrives in. Both handles take the average of their original
+/// lengths, so neither segment is stretched much further than the other to match
+pub fn join_chains(from: &mut [Vec3], to: &mut [Vec3]) {
+    let (&[.., incoming, join], &[start, outgoing, ..]) = (&*from, &*to) else {
+        return;
+    };
+    let direction = (join - incoming)
+        .try_normalize()
+        .or_else(|| (outgoing - start).try_normalize())
+        .unwrap_or(Vec3::X);
+    let length = (join.distance(incoming) + start.distance(outgoing)) / 2.0;
+    from[from.len() - 2] = join - direction * length;
+    to[0] = join;
+    to[1] = join + direction * length;
+}
+
+/// Bézier chain passing through every one of `waypoints`, following a cardinal spline. The tangent
+/// at each waypoint runs parallel to the line between its neighbours, scaled by `1 - tension`, so
+/// a tension of 0 gives a Catmull-Rom spline and 1 gives straight lines. Open ends take a phantom
+/// neighbour mirrored through the end point, while closed chains wrap around to the first waypoint.
+///
+/// `alpha` spaces the knots by the distance between waypoints raised to that power: 0 for the
+/// uniform spline, 0.5 for the centripetal spline, which never loops or forms cusps between
+/// closely spaced waypoints, and 1 for the chordal spline
+pub fn cardinal_chain(waypoints: &[Vec3], tension: f32, alpha: f32, closed: bool) -> Vec<Vec3> {
+    let n = waypoints.len();
+    if n < 2 {
+        return waypoints.to_vec();
+    }
+    let neighbour = |i: usize, offset: isize| -> Vec3 {
+        let j = i as isize + offset;
+        if closed {
+            waypoints[j.rem_euclid(n as isize) as usize]
+        } else if j < 0 {
+            2.0 * waypoints[0] - waypoints[1]
+        } else if j >= n as isize {
+            2.0 * waypoints[n - 1] - waypoints[n - 2]
+        } else {
+            waypoints[j as usize]
+        }
+    };
+    // Knot spacing, kept above 0 so repeated waypoints don't divide by zero
+    let knot = |a: Vec3, b: Vec3| a.distance(b).powf(alpha).max(f32::EPSILON);
+    let spans = if closed { n } else { n - 1 };
+    let mut chain = vec![waypoints[0]];
+    for i in 0..spans {
+        let [p0, p1, p2, p3] = [-1, 0, 1, 2].map(|offset| neighbour(i, offset));
+        let (d01, d12, d23) = (knot(p0, p1), knot(p1, p2), knot(p2, p3));
+        // Tangents at either end of the span, rescaled from the knot spacing to the span's 0..1.
+        // With uniform knots both reduce to half the line between the neighbours
+        let start_tangent = d12 * ((p1 - p0) / d01 - (p2 - p0) / (d01 + d12) + (p2 - p1) / d12);
+        let end_tangent = d12 * ((p2 - p1) / d12 - (p3 - p1) / (d12 + d23) + (p3 - p2) / d23);
+        // A Bézier handle a third of the tangent out reproduces the Hermite span exactly
+        chain.extend([
+            p1 + (1.0 - tension) * start_tangent / 3.0,
+            p2 - (1.0 - tension) * end_tangent / 3.0,
+            p2,
+        ]);
+    }
+    chain
+}
+
+/// Cumulative length along a chain of segments, sampled densely so distances can be mapped back to
+/// curve parameters
+pub struct ArcLengthTable {
+    segments: Vec<[Vec3; 4]>,
+    /// `(u, distance)` pairs where `u` is the segment index plus the `t` within that segment
+    samples: Vec<(f32, f32)>,
+}
+
+impl ArcLengthTable {
+    pub fn new(control_points: &[Vec3]) -> Self {
+        let segments = segments(control_points).collect::<Vec<_>>();
+        let mut samples = vec![];
+        let mut distance = 0.0;
+        let mut previous_point = segments.first().map(|[a, ..]| *a);
+        for (i, [a, b, c, d]) in segments.iter().enumerate() {
+            // The first sample of each segment is the last sample of the previous one
+            let first_sample = if i == 0 { 0 } else { 1 };
+            for j in first_sample..=ARC_LENGTH_SAMPLES {
+                let t = j as f32 / ARC_LENGTH_SAMPLES as f32;
+                let point = cubic_bezier(*a, *b, *c, *d, t);
+                if let Some(previous_point) = previous_point {
+                    distance += point.distance(previous_point);
+                }
+                previous_point = Some(point);
+                samples.push((i as f32 + t, distance));
+            }
+        }
+        ArcLengthTable { segments, samples }
+    }
+
+    /// Total length of the curve
+    pub fn length(&self) -> f32 {
+        self.samples.last().map_or(0.0, |(_, distance)| *distance)
+    }
+
+    /// Segment control points and the `t` within that segment at `distance` along the curve, or
+    /// `None` if the distance is outside the curve
+    pub fn segment_at_distance(&self, distance: f32) -> Option<([Vec3; 4], f32)> {
+        if self.samples.is_empty() || !(0.0..=self.length()).contains(&distance) {
+            return None;
+        }
+        let i = self
+            .samples
+            .partition_point(|(_, sample_distance)| *sample_distance < distance);
+        let u = if i == 0 {
+            self.samples[0].0
+        } else {
+            let (u0, d0) = self.samples[i - 1];
+            let (u1, d1) = self.samples[i];
+            let span = d1 - d0;
+            if span > 0.0 {
+                u0 + (u1 - u0) * (distance - d0) / span
+            } else {
+                u0
+            }
+        };
+        let segment = (u.floor() as usize).min(self.segments.len() - 1);
+        Some((self.segments[segment], u - segment as f32))
+    }
+
+    /// Point at `distance` along the curve, or `None` if the distance is outside the curve
+    pub fn point_at_distance(&self, distance: f32) -> Option<Vec3> {
+        self.segment_at_distance(distance)
+            .map(|([a, b, c, d], t)| cubic_bezier(a, b, c, d, t))
+    }
+
+    /// Frame (as in [`cubic_bezier_matrix`]) at `distance` along the curve, or `None` if the
+    /// distance is outside the curve
+    pub fn frame_at_distance(&self, distance: f32, up: Vec3) -> Option<Mat4> {
+        self.segment_at_distance(distance)
+            .map(|([a, b, c, d], t)| cubic_bezier_matrix(a, b, c, d, t, up))
+    }
+
+    /// Places where the curve comes back within `min_distance` of itself, as pairs of distances
+    /// along it, for spotting a road that will pass through itself. Points closer together along
+    /// the curve than `min_distance * π / 2` are neighbours rather than separate sections, since a
+    /// bend that tight is a hairpin the road can't get round without overlapping. For a `closed`
+    /// curve that's measured either way round the loop. Each sample reports only the nearest later
+    /// sample it clashes with. The curve is sampled every quarter of `min_distance`, and no more
+    /// than `OVERLAP_SAMPLES` times
+    pub fn close_approaches(&self, min_distance: f32, closed: bool) -> Vec<(f32, f32)> {
+        let length = self.length();
+        if min_distance <= 0.0 || length <= 0.0 {
+            return vec![];
+        }
+        let count = ((length / (min_distance / 4.0)).ceil() as usize).clamp(2, OVERLAP_SAMPLES);
+        let samples = (0..count)
+            .filter_map(|i| {
+                let distance = length * i as f32 / (count - 1) as f32;
+                Some((distance, self.point_at_distance(distance)?))
+            })
+            .collect::<Vec<_>>();
+        let min_separation = min_distance * FRAC_PI_2;
+        let separation = |a: f32, b: f32| {
+            let along = (a - b).abs();
+            if closed {
+                along.min(length - along)
+            } else {
+                along
+            }
+        };
+        samples
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &(distance, point))| {
+                samples[i + 1..]
+                    .iter()
+                    .filter(|&&(other_distance, _)| {
+                        separation(distance, other_distance) > min_separation
+                    })
+                    .map(|&(other_distance, other)| (other_distance, point.distance(other)))
+                    .filter(|&(_, gap)| gap < min_distance)
+                    .min_by(|x, y| x.1.total_cmp(&y.1))
+                    .map(|(other_distance, _)| (distance, other_distance))
+            })
+            .collect()
+    }
+}
+
+/// Most samples [`ArcLengthTable::close_approaches`] takes, which bounds its cost on a long curve
+const OVERLAP_SAMPLES: usize = 2048;
+
+/// Total length of the chain of segments in `control_points`. Zero if there isn't a whole segment
+pub fn curve_length(control_points: &[Vec3]) -> f32 {
+    ArcLengthTable::new(control_points).length()
+}
+
+/// Point at `distance` along the chain of segments in `control_points`, or `None` if the distance
+/// exceeds the length of the curve. Build an [`ArcLengthTable`] instead when sampling many
+/// distances on the same curve
+pub fn point_at_distance(control_points: &[Vec3], distance: f32) -> Option<Vec3> {
+    ArcLengthTable::new(control_points).point_at_distance(distance)
+}
+
+/// Frame at `distance` along the chain of segments in `control_points`, or `None` if the distance
+/// exceeds the length of the curve
+pub fn frame_at_distance(control_points: &[Vec3], distance: f32, up: Vec3) -> Option<Mat4> {
+    ArcLengthTable::new(control_points).frame_at_distance(distance, up)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::road::RoadBuilder;
+    use bevy::render::mesh::VertexAttributeValues;
+
+    const TOLERANCE: f32 = 1e-4;
+
+    fn assert_close(actual: Vec3, expected: Vec3) {
+        assert!(
+            actual.distance(expected) < TOLERANCE,
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    /// Two segments bending one way then the other, off the ground
+    fn s_bend() -> Vec<Vec3> {
+        vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.5, 2.0),
+            Vec3::new(3.0, 1.0, 2.0),
+            Vec3::new(4.0, 1.0, 0.0),
+            Vec3::new(5.0, 1.0, -2.0),
+            Vec3::new(7.0, 0.0, -2.0),
+            Vec3::new(8.0, 0.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn coincident_points_make_no_nan() {
+        let (p, q) = (Vec3::ZERO, Vec3::new(3.0, 0.0, 1.0));
+        // Handles dragged onto their anchors, and a segment collapsed to a point
+        for segment in [[p, p, q, q], [p, p, p, q], [p, q, q, q], [p, p, p, p]] {
+            let [a, b, c, d] = segment;
+            let samples = (0..=10)
+                .map(|i| (segment, i as f32 / 10.0))
+                .collect::<Vec<_>>();
+            for &(_, t) in &samples {
+                let direction = cubic_bezier_direction(a, b, c, d, t);
+                assert!(direction.is_normalized(), "{segment:?} at {t}: {direction}");
+                let frame = cubic_bezier_matrix(a, b, c, d, t, Vec3::Y);
+                assert!(frame.is_finite(), "{segment:?} at {t}: {frame}");
+            }
+            for mode in [FrameMode::WorldUp, FrameMode::ParallelTransport] {
+                for (position, rotation) in curve_frames(&samples, mode, Vec3::Y) {
+                    assert!(position.is_finite() && rotation.is_finite(), "{segment:?}");
+                }
+            }
+
+            let mesh = RoadBuilder::new().detail(10).build(&segment);
+            for attribute in [Mesh::ATTRIBUTE_POSITION, Mesh::ATTRIBUTE_NORMAL] {
+                let name = attribute.name;
+                let Some(VertexAttributeValues::Float32x3(values)) = mesh.attribute(attribute)
+                else {
+                    panic!("{segment:?} has no {name}");
+                };
+                assert!(!values.is_empty());
+                assert!(
+                    values.iter().flatten().all(|value| value.is_finite()),
+                    "{segment:?} has a NaN in its {name}"
+                );
+            }
+        }
+    }
+
+    /// Whether the chain crosses over itself, seen from above, checked along a dense polyline
+    fn crosses_itself(chain: &[Vec3]) -> bool {
+        let points = segments(chain)
+            .enumerate()
+            .flat_map(|(i, [a, b, c, d])| {
+                let first = if i == 0 { 0 } else { 1 };
+                (first..=64).map(move |j| {
+                    let point = cubic_bezier(a, b, c, d, j as f32 / 64.0);
+                    Vec2::new(point.x, point.z)
+                })
+            })
+            .collect::<Vec<_>>();
+        // Which side of the line through `a` and `b` that `c` is on
+        let side = |a: Vec2, b: Vec2, c: Vec2| (b - a).perp_dot(c - a);
+        let lines = points.windows(2).collect::<Vec<_>>();
+        (0..lines.len()).any(|i| {
+            // Earlier lines were already checked against this one, and the next shares its end
+            lines.iter().skip(i + 2).any(|other| {
+                let ([p, q], [r, s]) = ([lines[i][0], lines[i][1]], [other[0], other[1]]);
+                side(p, q, r) * side(p, q, s) < 0.0 && side(r, s, p) * side(r, s, q) < 0.0
+            })
+        })
+    }
+
+    #[test]
+    fn centripetal_spline_doesnt_loop() {
+        // A long straight with a small bump in it. The uniform spline overshoots the short spans
+        // and loops back over itself
+        let waypoints = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(2.1, 0.0, 0.1),
+            Vec3::new(2.2, 0.0, 0.0),
+            Vec3::new(4.0, 0.0, 0.0),
+        ];
+        assert!(crosses_itself(&cardinal_chain(&waypoints, 0.0, 0.0, false)));
+        assert!(!crosses_itself(&cardinal_chain(
+            &waypoints, 0.0, 0.5, false
+        )));
+    }
+
+    #[test]
+    fn split_halves_trace_the_original() {
+        let [a, b, c, d] = segments(&s_bend()).next().unwrap();
+        for split in [0.25, 0.5, 0.8] {
+            let ([e, f, g, h], [i, j, k, l]) = split_cubic(a, b, c, d, split);
+            for step in 0..=10 {
+                let t = step as f32 / 10.0;
+                // The halves run over 0..=split and split..=1 of the original
+                assert_close(
+                    cubic_bezier(e, f, g, h, t),
+                    cubic_bezier(a, b, c, d, t * split),
+                );
+                assert_close(
+                    cubic_bezier(i, j, k, l, t),
+                    cubic_bezier(a, b, c, d, split + t * (1.0 - split)),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn near_vertical_frames_stay_orthonormal() {
+        // Climbing almost straight up, then exactly straight up
+        for lean in [1e-4, 0.0] {
+            let [a, b, c, d] = [0.0, 1.0, 2.0, 3.0].map(|y| Vec3::new(lean * y, y, 0.0));
+            for step in 0..=10 {
+                let t = step as f32 / 10.0;
+                let (_, rotation) = cubic_bezier_quat(a, b, c, d, t, Vec3::Y);
+                let matrix = cubic_bezier_matrix(a, b, c, d, t, Vec3::Y);
+                assert!(
+                    rotation.is_finite() && matrix.is_finite(),
+                    "lean {lean} at {t}"
+                );
+                let axes =
+                    [matrix.x_axis, matrix.y_axis, matrix.z_axis].map(|axis| axis.truncate());
+                for (i, axis) in axes.iter().enumerate() {
+                    assert!(
+                        (axis.length() - 1.0).abs() < TOLERANCE,
+                        "lean {lean} at {t}"
+                    );
+                    for other in &axes[i + 1..] {
+                        assert!(axis.dot(*other).abs() < TOLERANCE, "lean {lean} at {t}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn insert_anchor_keeps_the_curve() {
+        let before = s_bend();
+        let mut after = before.clone();
+        let split = 0.3;
+        insert_anchor(&mut after, 1, split);
+        assert_eq!(after.len(), before.len() + 3);
+
+        let before_segments = segments(&before).collect::<Vec<_>>();
+        let after_segments = segments(&after).collect::<Vec<_>>();
+        for i in 0..=20 {
+            let t = i as f32 / 20.0;
+            let [a, b, c, d] = before_segments[0];
+            let [e, f, g, h] = after_segments[0];
+            assert_close(cubic_bezier(e, f, g, h, t), cubic_bezier(a, b, c, d, t));
+
+            // The split segment's samples land on one half or the other
+            let [a, b, c, d] = before_segments[1];
+            let expected = cubic_bezier(a, b, c, d, t);
+            let [e, f, g, h] = if t <= split {
+                after_segments[1]
+            } else {
+                after_segments[2]
+            };
+            let local_t = if t <= split {
+                t / split
+            } else {
+                (t - split) / (1.0 - split)
+            };
+            assert_close(cubic_bezier(e, f, g, h, local_t), expected);
+        }
+    }
+}
@@ -0,0 +1,752 @@
+use crate::{
+    banked_frame_at_distance, changed_meshes, conform_frame, curve_points, extrude_surfaces,
+    path_distances, road_material, road_slices, slice_frames, slice_textures, width_at, Config,
+    ControlPoint, Generated, Road, RoadSurface, Terrain, MIN_WIDTH,
+};
+use bevy::prelude::*;
+use bevy::render::mesh::PrimitiveTopology;
+use bevy::render::primitives::Aabb;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_panorbit_camera::PanOrbitCamera;
+use bezier_mesh::curve::{segments, ArcLengthTable};
+use bezier_mesh::mesh::MeshData;
+use bezier_mesh::profile::{profile_bottom, profile_vertices, ProfileKind, ROAD_SURFACE_HEIGHT};
+use itertools::Itertools;
+use std::collections::HashSet;
+use std::f32::consts::TAU;
+
+/// Post standing along the edge of the road, spawned as a child of the `Generated` entity
+#[derive(Component, Default, Debug)]
+pub struct GuardrailPost;
+
+/// Painted lines along the road surface, spawned as a child of the `Generated` entity
+#[derive(Component, Default, Debug)]
+pub struct LaneMarking;
+
+/// Striped band painted across the road surface, spawned as a child of the `Generated` entity
+#[derive(Component, Default, Debug)]
+pub struct Crosswalk;
+
+/// Surface of a road's low detail copy, drawn in place of its `RoadSurface`s from far away
+#[derive(Component, Debug)]
+pub struct RoadLod;
+
+/// Outline of the road flattened onto the ground, spawned as a child of the `Generated` entity
+#[derive(Component, Default, Debug)]
+pub struct Footprint;
+
+/// Column holding the road up, spawned as a child of the `Generated` entity
+#[derive(Component, Default, Debug)]
+pub struct BridgePillar;
+
+/// Roads whose surfaces were rebuilt, with their old children of type `C` despawned
+fn rebuilt_roads<C: Component>(
+    commands: &mut Commands,
+    mesh_events: &mut EventReader<AssetEvent<Mesh>>,
+    roads: impl Iterator<Item = Entity>,
+    surface_q: &Query<(&Handle<Mesh>, &Parent), With<RoadSurface>>,
+    child_q: &Query<(Entity, &Parent), With<C>>,
+) -> Vec<Entity> {
+    let changed_meshes = changed_meshes(mesh_events);
+    let rebuilt = roads
+        .filter(|&road| {
+            surface_q
+                .iter()
+                .any(|(handle, parent)| parent.get() == road && changed_meshes.contains(handle))
+        })
+        .collect::<Vec<_>>();
+    for (child, parent) in child_q.iter() {
+        if rebuilt.contains(&parent.get()) {
+            commands.entity(child).despawn_recursive();
+        }
+    }
+    rebuilt
+}
+
+/// Where posts stand across the road, in the profile's local space: on top of each curb
+const GUARDRAIL_OFFSETS: [Vec3; 2] = [Vec3::new(-0.4, 0.3, 0.0), Vec3::new(0.4, 0.3, 0.0)];
+
+const GUARDRAIL_POST_SIZE: Vec3 = Vec3::new(0.04, 0.25, 0.04);
+
+/// Respawns the posts along both curbs whenever the road is rebuilt
+#[allow(clippy::too_many_arguments)]
+pub fn update_guardrails(
+    mut commands: Commands,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    config: Res<Config>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    generated_q: Query<Entity, With<Generated>>,
+    surface_q: Query<(&Handle<Mesh>, &Parent), With<RoadSurface>>,
+    terrain_q: Query<(&Handle<Mesh>, &GlobalTransform), With<Terrain>>,
+    post_q: Query<(Entity, &Parent), With<GuardrailPost>>,
+    mut post_assets: Local<Option<(Handle<Mesh>, Handle<StandardMaterial>)>>,
+) {
+    let (post_mesh, post_material) = post_assets
+        .get_or_insert_with(|| {
+            let [x, y, z] = GUARDRAIL_POST_SIZE.to_array();
+            (
+                meshes.add(shape::Box::new(x, y, z).into()),
+                materials.add(Color::GRAY.into()),
+            )
+        })
+        .clone();
+
+    for road in rebuilt_roads(
+        &mut commands,
+        &mut mesh_events,
+        generated_q.iter(),
+        &surface_q,
+        &post_q,
+    ) {
+        let Some(spacing) = config.guardrails.filter(|spacing| *spacing > 0.0) else {
+            continue;
+        };
+        // Only the road has curbs to stand posts on
+        if !matches!(config.profile_kind, ProfileKind::Road) {
+            continue;
+        }
+
+        let table = ArcLengthTable::new(&curve_points(&point_q, road, &config));
+        let length = table.length();
+        // The last post is pulled back to the end of the road when the spacing doesn't divide it
+        let post_count = (length / spacing).ceil() as usize + 1;
+        commands.entity(road).with_children(|parent| {
+            for i in 0..post_count {
+                let distance = (i as f32 * spacing).min(length);
+                let Some(frame) = banked_frame_at_distance(&table, distance, &config) else {
+                    continue;
+                };
+                let frame = conform_frame(frame, &config, &meshes, &terrain_q);
+                let width = width_at(&config.width_profile, distance / length.max(f32::EPSILON));
+                for offset in GUARDRAIL_OFFSETS {
+                    // Boxes are centred on their origin, so lift each post to stand on the curb
+                    let base = offset * (config.profile_scale * Vec2::new(width, 1.0)).extend(1.0)
+                        + Vec3::new(config.lateral_offset, GUARDRAIL_POST_SIZE.y / 2.0, 0.0);
+                    parent.spawn((
+                        GuardrailPost,
+                        PbrBundle {
+                            mesh: post_mesh.clone(),
+                            material: post_material.clone(),
+                            transform: Transform::from_matrix(frame * Mat4::from_translation(base)),
+                            ..default()
+                        },
+                    ));
+                }
+            }
+        });
+    }
+}
+
+const PILLAR_RADIUS: f32 = 0.08;
+
+/// Respawns the columns from under each road down to the ground when it's rebuilt
+#[allow(clippy::too_many_arguments)]
+pub fn update_pillars(
+    mut commands: Commands,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    config: Res<Config>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    generated_q: Query<Entity, With<Generated>>,
+    surface_q: Query<(&Handle<Mesh>, &Parent), With<RoadSurface>>,
+    terrain_q: Query<(&Handle<Mesh>, &GlobalTransform), With<Terrain>>,
+    pillar_q: Query<(Entity, &Parent), With<BridgePillar>>,
+    mut pillar_assets: Local<Option<(Handle<Mesh>, Handle<StandardMaterial>)>>,
+) {
+    // One unit tall, and stretched to the height of each column
+    let (pillar_mesh, pillar_material) = pillar_assets
+        .get_or_insert_with(|| {
+            (
+                meshes.add(
+                    shape::Cylinder {
+                        radius: PILLAR_RADIUS,
+                        height: 1.0,
+                        ..default()
+                    }
+                    .into(),
+                ),
+                materials.add(Color::GRAY.into()),
+            )
+        })
+        .clone();
+
+    for road in rebuilt_roads(
+        &mut commands,
+        &mut mesh_events,
+        generated_q.iter(),
+        &surface_q,
+        &pillar_q,
+    ) {
+        let Some(pillars) = config
+            .pillars
+            .as_ref()
+            .filter(|pillars| pillars.spacing > 0.0)
+        else {
+            continue;
+        };
+
+        let table = ArcLengthTable::new(&curve_points(&point_q, road, &config));
+        let length = table.length();
+        let underside = Vec3::Y * profile_bottom(&config.profile_kind) * config.profile_scale.y;
+        commands.entity(road).with_children(|parent| {
+            for i in 0..=(length / pillars.spacing) as usize {
+                let Some(frame) =
+                    banked_frame_at_distance(&table, i as f32 * pillars.spacing, &config)
+                else {
+                    continue;
+                };
+                let frame = conform_frame(frame, &config, &meshes, &terrain_q);
+                let top = frame.transform_point3(underside + Vec3::X * config.lateral_offset);
+                let height = top.y - pillars.ground;
+                if height <= 0.0 {
+                    continue;
+                }
+                parent.spawn((
+                    BridgePillar,
+                    PbrBundle {
+                        mesh: pillar_mesh.clone(),
+                        material: pillar_material.clone(),
+                        transform: Transform::from_translation(top - Vec3::Y * height / 2.0)
+                            .with_scale(Vec3::new(1.0, height, 1.0)),
+                        ..default()
+                    },
+                ));
+            }
+        });
+    }
+}
+
+/// Height of lane markings above the road surface, to keep them from z-fighting
+const MARKING_LIFT: f32 = 0.002;
+/// Rows in the generated dash texture
+const DASH_TEXTURE_SIZE: u32 = 64;
+
+/// Rebuilds the dashed lines along each road's lanes whenever it's rebuilt
+#[allow(clippy::too_many_arguments)]
+pub fn update_lane_markings(
+    mut commands: Commands,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    config: Res<Config>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    generated_q: Query<(Entity, &Road), With<Generated>>,
+    surface_q: Query<(&Handle<Mesh>, &Parent), With<RoadSurface>>,
+    terrain_q: Query<(&Handle<Mesh>, &GlobalTransform), With<Terrain>>,
+    marking_q: Query<(Entity, &Parent), With<LaneMarking>>,
+    mut marking_material: Local<Option<Handle<StandardMaterial>>>,
+) {
+    let markings = config
+        .lane_markings
+        .as_ref()
+        .filter(|markings| markings.width > 0.0 && markings.dash_length > 0.0)
+        .filter(|_| matches!(config.profile_kind, ProfileKind::Road));
+    if config.is_changed() {
+        if let Some(markings) = markings {
+            // Opaque where the dash is and clear through the gap, repeating along V
+            let dash_rows = ((markings.dash_length
+                / (markings.dash_length + markings.gap_length.max(0.0)))
+                * DASH_TEXTURE_SIZE as f32)
+                .round() as u32;
+            let data = (0..DASH_TEXTURE_SIZE)
+                .flat_map(|row| {
+                    let alpha = if row < dash_rows { 255 } else { 0 };
+                    [255, 255, 255, alpha]
+                })
+                .collect();
+            let texture = images.add(Image::new(
+                Extent3d {
+                    width: 1,
+                    height: DASH_TEXTURE_SIZE,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                data,
+                TextureFormat::Rgba8UnormSrgb,
+            ));
+            *marking_material = Some(materials.add(StandardMaterial {
+                base_color_texture: Some(texture),
+                alpha_mode: AlphaMode::Mask(0.5),
+                // Pulls the markings forward in the depth buffer on top of the lift
+                depth_bias: 1.0,
+                ..default()
+            }));
+        }
+    }
+
+    let roads = rebuilt_roads(
+        &mut commands,
+        &mut mesh_events,
+        generated_q.iter().map(|(road, _)| road),
+        &surface_q,
+        &marking_q,
+    );
+    for (road, road_settings) in generated_q.iter_many(roads) {
+        let (Some(markings), Some(material)) = (markings, marking_material.as_ref()) else {
+            continue;
+        };
+
+        let slices = road_slices(
+            &curve_points(&point_q, road, &config),
+            road_settings.detail,
+            &config,
+        );
+        let frames = slice_frames(&slices, &config, &meshes, &terrain_q);
+        let distances = path_distances(frames.iter().map(|frame| frame.w_axis.truncate()));
+        let length = distances.last().copied().unwrap_or_default();
+        if frames.len() < 2 {
+            continue;
+        }
+        // Across the lanes from -0.2 to 0.2, marking the middle and every boundary between lanes
+        let lanes = config.lanes.max(1);
+        let offsets = (1..lanes)
+            .map(|lane| -0.2 + 0.4 * lane as f32 / lanes as f32)
+            .chain([0.0])
+            .sorted_by(f32::total_cmp)
+            .dedup_by(|a, b| (a - b).abs() < f32::EPSILON)
+            .collect::<Vec<_>>();
+        let period = markings.dash_length + markings.gap_length.max(0.0);
+        let height = ROAD_SURFACE_HEIGHT * config.profile_scale.y + MARKING_LIFT;
+        let frames = frames.into_iter().zip(distances).collect::<Vec<_>>();
+
+        let mut data = MeshData {
+            topology: PrimitiveTopology::TriangleList,
+            ..default()
+        };
+        let half_width = markings.width / 2.0;
+        for offset in offsets {
+            ribbon_mesh(
+                &mut data,
+                &frames,
+                length,
+                [(offset, -half_width), (offset, half_width)],
+                height,
+                1.0 / period,
+                &config,
+            );
+        }
+        let mesh = meshes.add(data.into_mesh());
+        commands.entity(road).with_children(|parent| {
+            parent.spawn((
+                LaneMarking,
+                PbrBundle {
+                    mesh,
+                    material: material.clone(),
+                    ..default()
+                },
+            ));
+        });
+    }
+}
+
+/// Length of a crosswalk along the road
+const CROSSWALK_DEPTH: f32 = 0.3;
+/// Slices across each crosswalk, so it follows the road round bends
+const CROSSWALK_SLICES: usize = 4;
+/// Painted stripes across the width of a crosswalk, each as wide as the gap after it
+const CROSSWALK_STRIPES: u32 = 6;
+
+/// Rebuilds the striped strips at `Config.crosswalks` whenever a road is rebuilt
+#[allow(clippy::too_many_arguments)]
+pub fn update_crosswalks(
+    mut commands: Commands,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    config: Res<Config>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    generated_q: Query<Entity, With<Generated>>,
+    surface_q: Query<(&Handle<Mesh>, &Parent), With<RoadSurface>>,
+    terrain_q: Query<(&Handle<Mesh>, &GlobalTransform), With<Terrain>>,
+    crosswalk_q: Query<(Entity, &Parent), With<Crosswalk>>,
+    mut crosswalk_material: Local<Option<Handle<StandardMaterial>>>,
+) {
+    // Opaque stripes and clear gaps alternating across U
+    let material = crosswalk_material
+        .get_or_insert_with(|| {
+            let data = (0..CROSSWALK_STRIPES * 2)
+                .flat_map(|column| {
+                    let alpha = if column % 2 == 0 { 255 } else { 0 };
+                    [255, 255, 255, alpha]
+                })
+                .collect();
+            let texture = images.add(Image::new(
+                Extent3d {
+                    width: CROSSWALK_STRIPES * 2,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                data,
+                TextureFormat::Rgba8UnormSrgb,
+            ));
+            materials.add(StandardMaterial {
+                base_color_texture: Some(texture),
+                alpha_mode: AlphaMode::Mask(0.5),
+                // Pulls the crosswalks forward in the depth buffer on top of the lift
+                depth_bias: 1.0,
+                ..default()
+            })
+        })
+        .clone();
+
+    for road in rebuilt_roads(
+        &mut commands,
+        &mut mesh_events,
+        generated_q.iter(),
+        &surface_q,
+        &crosswalk_q,
+    ) {
+        if config.crosswalks.is_empty() || !matches!(config.profile_kind, ProfileKind::Road) {
+            continue;
+        }
+
+        let table = ArcLengthTable::new(&curve_points(&point_q, road, &config));
+        let length = table.length();
+        let height = ROAD_SURFACE_HEIGHT * config.profile_scale.y + MARKING_LIFT;
+        let mut data = MeshData {
+            topology: PrimitiveTopology::TriangleList,
+            ..default()
+        };
+        for &center in &config.crosswalks {
+            if !(0.0..=length).contains(&center) {
+                warn!("Crosswalk at {center} is past the end of a road {length} long");
+                continue;
+            }
+            // Clamped at the ends, so a crosswalk there is squashed rather than hanging off
+            let start = (center - CROSSWALK_DEPTH / 2.0).max(0.0);
+            let end = (center + CROSSWALK_DEPTH / 2.0).min(length);
+            let frames = (0..CROSSWALK_SLICES)
+                .filter_map(|i| {
+                    let distance = start + (end - start) * i as f32 / (CROSSWALK_SLICES - 1) as f32;
+                    let frame = banked_frame_at_distance(&table, distance, &config)?;
+                    Some((conform_frame(frame, &config, &meshes, &terrain_q), distance))
+                })
+                .collect::<Vec<_>>();
+            // The road surface runs from -0.2 to 0.2 across
+            ribbon_mesh(
+                &mut data,
+                &frames,
+                length,
+                [(-0.2, 0.0), (0.2, 0.0)],
+                height,
+                0.0,
+                &config,
+            );
+        }
+        if data.indices.is_empty() {
+            continue;
+        }
+        let mesh = meshes.add(data.into_mesh());
+        commands.entity(road).with_children(|parent| {
+            parent.spawn((
+                Crosswalk,
+                PbrBundle {
+                    mesh,
+                    material: material.clone(),
+                    ..default()
+                },
+            ));
+        });
+    }
+}
+
+/// Rebuilds the hidden low detail copy of each road whenever it's rebuilt
+#[allow(clippy::too_many_arguments)]
+pub fn update_lods(
+    mut commands: Commands,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+    config: Res<Config>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    generated_q: Query<(Entity, &Road), With<Generated>>,
+    surface_q: Query<(&Handle<Mesh>, &Parent), With<RoadSurface>>,
+    terrain_q: Query<(&Handle<Mesh>, &GlobalTransform), With<Terrain>>,
+    lod_q: Query<(Entity, &Parent), With<RoadLod>>,
+    mut lod_buffers: Local<MeshData>,
+) {
+    let roads = rebuilt_roads(
+        &mut commands,
+        &mut mesh_events,
+        generated_q.iter().map(|(road, _)| road),
+        &surface_q,
+        &lod_q,
+    );
+    for (road, road_settings) in generated_q.iter_many(roads) {
+        let Some(detail) = config.generate_lod else {
+            continue;
+        };
+
+        // Segments share their end slices, so n segments take n * (detail - 1) + 1 slices
+        let control_points = curve_points(&point_q, road, &config);
+        let table = ArcLengthTable::new(&control_points);
+        let slice_count = segments(&control_points).count() * (detail.max(2) - 1) + 1;
+        let slices = (0..slice_count)
+            .filter_map(|i| {
+                table.segment_at_distance(table.length() * i as f32 / (slice_count - 1) as f32)
+            })
+            .collect::<Vec<_>>();
+        if slices.len() < 2 {
+            continue;
+        }
+        let frames = slice_frames(&slices, &config, &meshes, &terrain_q);
+        let textures = slice_textures(&slices, &road_settings.segment_textures);
+        let mut surfaces = vec![];
+        extrude_surfaces(
+            &slices,
+            &frames,
+            &config,
+            &textures,
+            &mut lod_buffers,
+            |group, texture, mesh_data| {
+                let surface = RoadSurface {
+                    group,
+                    texture: texture.map(String::from),
+                };
+                surfaces.push((
+                    meshes.add(mesh_data.clone().into_mesh()),
+                    materials.add(road_material(&config, &asset_server, &surface)),
+                ));
+            },
+        );
+        commands.entity(road).with_children(|parent| {
+            for (mesh, material) in surfaces {
+                parent.spawn((
+                    RoadLod,
+                    PbrBundle {
+                        mesh,
+                        material,
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                ));
+            }
+        });
+    }
+}
+
+/// Shows low detail copies in place of the full surfaces beyond `Config.lod_distance`
+#[allow(clippy::type_complexity)]
+pub fn swap_lods(
+    config: Res<Config>,
+    camera_q: Query<&GlobalTransform, With<PanOrbitCamera>>,
+    lod_q: Query<(&Parent, &Aabb, &GlobalTransform), With<RoadLod>>,
+    mut visibility_q: Query<
+        (&Parent, &mut Visibility, Option<&RoadLod>),
+        Or<(With<RoadSurface>, With<RoadLod>)>,
+    >,
+) {
+    let Ok(camera) = camera_q.get_single() else {
+        return;
+    };
+    let camera = camera.translation();
+    // Roads whose copy is far enough away to be drawn instead
+    let far_roads = lod_q
+        .iter()
+        .filter(|(_, aabb, transform)| {
+            let center = transform.transform_point(aabb.center.into());
+            let reach = transform
+                .compute_matrix()
+                .transform_vector3(aabb.half_extents.into());
+            camera.distance(center) - reach.length() > config.lod_distance
+        })
+        .map(|(parent, ..)| parent.get())
+        .collect::<HashSet<_>>();
+    for (parent, mut visibility, lod) in visibility_q.iter_mut() {
+        let far = config.generate_lod.is_some() && far_roads.contains(&parent.get());
+        let wanted = if far == lod.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+/// Rebuilds the footprint of each road whenever it's rebuilt
+#[allow(clippy::too_many_arguments)]
+pub fn update_footprints(
+    mut commands: Commands,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    config: Res<Config>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    generated_q: Query<(Entity, &Road), With<Generated>>,
+    surface_q: Query<(&Handle<Mesh>, &Parent), With<RoadSurface>>,
+    terrain_q: Query<(&Handle<Mesh>, &GlobalTransform), With<Terrain>>,
+    footprint_q: Query<(Entity, &Parent), With<Footprint>>,
+    mut footprint_material: Local<Option<Handle<StandardMaterial>>>,
+) {
+    let roads = rebuilt_roads(
+        &mut commands,
+        &mut mesh_events,
+        generated_q.iter().map(|(road, _)| road),
+        &surface_q,
+        &footprint_q,
+    );
+    for (road, road_settings) in generated_q.iter_many(roads) {
+        if !config.generate_footprint {
+            continue;
+        }
+        let slices = road_slices(
+            &curve_points(&point_q, road, &config),
+            road_settings.detail,
+            &config,
+        );
+        let frames = slice_frames(&slices, &config, &meshes, &terrain_q);
+        if frames.len() < 2 {
+            continue;
+        }
+        let mesh = meshes.add(road_footprint(&frames, &config));
+        let material = footprint_material
+            .get_or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: Color::rgba(0.0, 0.0, 0.0, 0.4),
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    // Twisted stretches of road land on the ground facing down
+                    cull_mode: None,
+                    ..default()
+                })
+            })
+            .clone();
+        commands.entity(road).with_children(|parent| {
+            parent.spawn((
+                Footprint,
+                PbrBundle {
+                    mesh,
+                    material,
+                    ..default()
+                },
+            ));
+        });
+    }
+}
+
+/// Strip between the profile's outer edges, projected straight down to Y = 0
+pub fn road_footprint(frames: &[Mat4], config: &Config) -> Mesh {
+    let distances = path_distances(frames.iter().map(|frame| frame.w_axis.truncate()));
+    let length = distances.last().copied().unwrap_or_default();
+    // The shoulders, where the profile reaches furthest either side
+    let (left, right) = profile_vertices(&config.profile_kind, config.lanes, 0.0)
+        .iter()
+        .map(|vertex| vertex.point.x)
+        .fold((f32::MAX, f32::MIN), |(min, max), x| {
+            (min.min(x), max.max(x))
+        });
+
+    let mut data = MeshData {
+        topology: PrimitiveTopology::TriangleList,
+        ..default()
+    };
+    let frames = frames.iter().copied().zip(distances).collect::<Vec<_>>();
+    ribbon_mesh(
+        &mut data,
+        &frames,
+        length,
+        [(left, 0.0), (right, 0.0)],
+        0.0,
+        1.0 / length.max(f32::EPSILON),
+        config,
+    );
+    for (position, normal) in data.positions.iter_mut().zip(&mut data.normals) {
+        position.y = 0.0;
+        *normal = Vec3::Y;
+    }
+    data.into_mesh()
+}
+
+/// Appends a strip between `edges` along `frames`, twisted and stretched like the road
+pub fn ribbon_mesh(
+    data: &mut MeshData,
+    frames: &[(Mat4, f32)],
+    length: f32,
+    edges: [(f32, f32); 2],
+    height: f32,
+    v_scale: f32,
+    config: &Config,
+) {
+    let first = data.vertex_count() as u32;
+    for &(frame, distance) in frames {
+        let twist = config.twist_turns * TAU * distance / length.max(f32::EPSILON);
+        let frame = frame * Mat4::from_rotation_z(twist);
+        let width = width_at(&config.width_profile, distance / length.max(f32::EPSILON));
+        let scale = config.profile_scale.x.max(MIN_WIDTH) * width;
+        for ((x, offset), u) in edges.into_iter().zip([0.0, 1.0]) {
+            let point = Vec3::new(x * scale + offset + config.lateral_offset, height, 0.0);
+            data.positions.push(frame.transform_point3(point));
+            data.normals
+                .push(frame.transform_vector3(Vec3::Y).normalize_or_zero());
+            data.uvs.push(Vec2::new(u, distance * v_scale));
+        }
+    }
+    for i in 0..(frames.len() as u32).saturating_sub(1) {
+        let [left, right] = [first + 2 * i, first + 2 * i + 1];
+        let [next_left, next_right] = [left + 2, right + 2];
+        data.indices
+            .extend([left, right, next_left, right, next_right, next_left]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{bend, road_config};
+    use bevy::render::mesh::VertexAttributeValues;
+
+    #[test]
+    fn ribbons_append_without_sharing_vertices() {
+        let config = road_config();
+        let (_, frames) = bend(5);
+        let distances = path_distances(frames.iter().map(|frame| frame.w_axis.truncate()));
+        let length = *distances.last().unwrap();
+        let frames = frames.into_iter().zip(distances).collect::<Vec<_>>();
+        let mut data = MeshData::default();
+        for offset in [-0.1, 0.1] {
+            ribbon_mesh(
+                &mut data,
+                &frames,
+                length,
+                [(offset, -0.01), (offset, 0.01)],
+                0.0,
+                1.0,
+                &config,
+            );
+        }
+        let per_ribbon = 2 * frames.len() as u32;
+        assert_eq!(data.vertex_count() as u32, 2 * per_ribbon);
+        // Two triangles between each pair of slices
+        assert_eq!(data.indices.len(), 2 * 6 * (frames.len() - 1));
+        let (first, second) = data.indices.split_at(data.indices.len() / 2);
+        assert!(first.iter().all(|&i| i < per_ribbon));
+        assert!(second
+            .iter()
+            .all(|&i| (per_ribbon..2 * per_ribbon).contains(&i)));
+        for pair in data.uvs.chunks_exact(2) {
+            assert_eq!([pair[0].x, pair[1].x], [0.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn footprint_lies_flat_on_the_ground() {
+        let (_, frames) = bend(5);
+        let footprint = road_footprint(&frames, &road_config());
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            footprint.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("footprint has no positions");
+        };
+        assert_eq!(positions.len(), 2 * frames.len());
+        assert!(positions.iter().all(|position| position[1] == 0.0));
+    }
+}
@@ -0,0 +1,385 @@
+use crate::curve::{cubic_bezier, cubic_bezier_tangent, ArcLengthTable};
+use bevy::prelude::*;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Writes the centerline of the chain in `control_points` to a CSV file at `path`, as `samples`
+/// points spaced evenly by arc length from one end to the other. Each row holds the sample's index,
+/// position and unit tangent. Only the header is written if there isn't a whole segment
+pub fn export_centerline_csv(
+    control_points: &[Vec3],
+    path: impl AsRef<Path>,
+    samples: usize,
+) -> io::Result<()> {
+    let table = ArcLengthTable::new(control_points);
+    let length = table.length();
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "index,x,y,z,tangent_x,tangent_y,tangent_z")?;
+    // Always take in both ends
+    let samples = samples.max(2);
+    for i in 0..samples {
+        let distance = length * i as f32 / (samples - 1) as f32;
+        let Some(([a, b, c, d], t)) = table.segment_at_distance(distance) else {
+            continue;
+        };
+        let point = cubic_bezier(a, b, c, d, t);
+        let tangent = cubic_bezier_tangent(a, b, c, d, t).normalize_or_zero();
+        writeln!(
+            writer,
+            "{i},{},{},{},{},{},{}",
+            point.x, point.y, point.z, tangent.x, tangent.y, tangent.z
+        )?;
+    }
+    writer.flush()
+}
+
+/// One mesh to write out with [`export_gltf`], placed by `transform`
+#[cfg(feature = "gltf")]
+pub struct GltfPart<'a> {
+    pub name: String,
+    pub mesh: &'a Mesh,
+    pub transform: Mat4,
+    pub material: GltfMaterial,
+}
+
+/// The parts of a material that carry over to glTF, as a metallic-roughness material
+#[cfg(feature = "gltf")]
+#[derive(Clone, Debug)]
+pub struct GltfMaterial {
+    pub base_color: Color,
+    /// Base colour texture, as a URI relative to the exported file
+    pub texture: Option<String>,
+    pub perceptual_roughness: f32,
+    pub metallic: f32,
+    pub double_sided: bool,
+}
+
+/// Writes `parts` to a binary glTF (`.glb`) file at `path`, each as its own node with a single
+/// primitive and material, so the surfaces of a road stay separate. Positions, normals, UVs and
+/// indices are written, with strips unpacked into triangle lists. Parts without positions or
+/// triangles are left out, and missing normals and UVs are filled with zeros. Textures are
+/// referenced by URI rather than embedded
+#[cfg(feature = "gltf")]
+pub fn export_gltf(path: impl AsRef<Path>, parts: &[GltfPart]) -> io::Result<()> {
+    use crate::mesh::triangle_list;
+    use bevy::render::mesh::VertexAttributeValues;
+    use gltf_json as json;
+    use json::validation::Checked::Valid;
+    use std::collections::HashMap;
+
+    let mut root = json::Root {
+        asset: json::Asset {
+            generator: Some("bezier_mesh".to_string()),
+            ..default()
+        },
+        ..default()
+    };
+    let mut data: Vec<u8> = vec![];
+    // Appends a view of `bytes` to the binary chunk, and an accessor reading it
+    let mut push_accessor = |root: &mut json::Root,
+                             bytes: &[u8],
+                             count: usize,
+                             component_type: json::accessor::ComponentType,
+                             type_: json::accessor::Type,
+                             target: json::buffer::Target,
+                             bounds: Option<(Vec3, Vec3)>| {
+        let view = json::buffer::View {
+            buffer: json::Index::new(0),
+            byte_length: bytes.len() as u32,
+            byte_offset: Some(data.len() as u32),
+            byte_stride: None,
+            name: None,
+            target: Some(Valid(target)),
+            extensions: None,
+            extras: Default::default(),
+        };
+        data.extend_from_slice(bytes);
+        root.buffer_views.push(view);
+        root.accessors.push(json::Accessor {
+            buffer_view: Some(json::Index::new(root.buffer_views.len() as u32 - 1)),
+            byte_offset: 0,
+            count: count as u32,
+            component_type: Valid(json::accessor::GenericComponentType(component_type)),
+            extensions: None,
+            extras: Default::default(),
+            type_: Valid(type_),
+            min: bounds.map(|(min, _)| json::Value::from(min.to_array().to_vec())),
+            max: bounds.map(|(_, max)| json::Value::from(max.to_array().to_vec())),
+            name: None,
+            normalized: false,
+            sparse: None,
+        });
+        json::Index::new(root.accessors.len() as u32 - 1)
+    };
+    fn floats<const N: usize>(values: &[[f32; N]]) -> Vec<u8> {
+        values
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect()
+    }
+
+    for part in parts {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            part.mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            continue;
+        };
+        let Some(indices) = triangle_list(part.mesh) else {
+            continue;
+        };
+        let count = positions.len();
+        let normals = match part.mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float32x3(normals)) => normals.clone(),
+            _ => vec![[0.0; 3]; count],
+        };
+        let uvs = match part.mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(uvs)) => uvs.clone(),
+            _ => vec![[0.0; 2]; count],
+        };
+        // glTF requires the bounds of every position accessor
+        let bounds = positions.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), &position| (min.min(position.into()), max.max(position.into())),
+        );
+
+        use json::accessor::{ComponentType, Type};
+        use json::buffer::Target::{ArrayBuffer, ElementArrayBuffer};
+        let position_accessor = push_accessor(
+            &mut root,
+            &floats(positions),
+            count,
+            ComponentType::F32,
+            Type::Vec3,
+            ArrayBuffer,
+            Some(bounds),
+        );
+        let normal_accessor = push_accessor(
+            &mut root,
+            &floats(&normals),
+            count,
+            ComponentType::F32,
+            Type::Vec3,
+            ArrayBuffer,
+            None,
+        );
+        let uv_accessor = push_accessor(
+            &mut root,
+            &floats(&uvs),
+            count,
+            ComponentType::F32,
+            Type::Vec2,
+            ArrayBuffer,
+            None,
+        );
+        let index_bytes = indices
+            .iter()
+            .flat_map(|i| i.to_le_bytes())
+            .collect::<Vec<_>>();
+        let index_accessor = push_accessor(
+            &mut root,
+            &index_bytes,
+            indices.len(),
+            ComponentType::U32,
+            Type::Scalar,
+            ElementArrayBuffer,
+            None,
+        );
+
+        let base_color_texture = part.material.texture.as_ref().map(|uri| {
+            root.images.push(json::Image {
+                buffer_view: None,
+                mime_type: None,
+                name: None,
+                uri: Some(uri.clone()),
+                extensions: None,
+                extras: Default::default(),
+            });
+            root.textures.push(json::Texture {
+                name: None,
+                sampler: None,
+                source: json::Index::new(root.images.len() as u32 - 1),
+                extensions: None,
+                extras: Default::default(),
+            });
+            json::texture::Info {
+                index: json::Index::new(root.textures.len() as u32 - 1),
+                tex_coord: 0,
+                extensions: None,
+                extras: Default::default(),
+            }
+        });
+        root.materials.push(json::Material {
+            name: Some(part.name.clone()),
+            double_sided: part.material.double_sided,
+            pbr_metallic_roughness: json::material::PbrMetallicRoughness {
+                base_color_factor: json::material::PbrBaseColorFactor(
+                    part.material.base_color.as_linear_rgba_f32(),
+                ),
+                base_color_texture,
+                metallic_factor: json::material::StrengthFactor(part.material.metallic),
+                roughness_factor: json::material::StrengthFactor(
+                    part.material.perceptual_roughness,
+                ),
+                ..default()
+            },
+            ..default()
+        });
+
+        root.meshes.push(json::Mesh {
+            extensions: None,
+            extras: Default::default(),
+            name: Some(part.name.clone()),
+            primitives: vec![json::mesh::Primitive {
+                attributes: HashMap::from([
+                    (Valid(json::mesh::Semantic::Positions), position_accessor),
+                    (Valid(json::mesh::Semantic::Normals), normal_accessor),
+                    (Valid(json::mesh::Semantic::TexCoords(0)), uv_accessor),
+                ]),
+                extensions: None,
+                extras: Default::default(),
+                indices: Some(index_accessor),
+                material: Some(json::Index::new(root.materials.len() as u32 - 1)),
+                mode: Valid(json::mesh::Mode::Triangles),
+                targets: None,
+            }],
+            weights: None,
+        });
+        root.nodes.push(json::Node {
+            camera: None,
+            children: None,
+            extensions: None,
+            extras: Default::default(),
+            matrix: (part.transform != Mat4::IDENTITY).then(|| part.transform.to_cols_array()),
+            mesh: Some(json::Index::new(root.meshes.len() as u32 - 1)),
+            name: Some(part.name.clone()),
+            rotation: None,
+            scale: None,
+            translation: None,
+            skin: None,
+            weights: None,
+        });
+    }
+
+    root.scenes.push(json::Scene {
+        extensions: None,
+        extras: Default::default(),
+        name: None,
+        nodes: (0..root.nodes.len() as u32).map(json::Index::new).collect(),
+    });
+    root.scene = Some(json::Index::new(0));
+    // Chunks are padded to 4 bytes, the JSON with spaces and the binary data with zeros
+    data.resize((data.len() + 3) & !3, 0);
+    // A buffer can't be empty, so one is only written when there's something to put in it
+    if !data.is_empty() {
+        root.buffers.push(json::Buffer {
+            byte_length: data.len() as u32,
+            name: None,
+            uri: None,
+            extensions: None,
+            extras: Default::default(),
+        });
+    }
+    let mut json = json::serialize::to_vec(&root).map_err(io::Error::from)?;
+    json.resize((json.len() + 3) & !3, b' ');
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    let bin_length = if data.is_empty() { 0 } else { 8 + data.len() };
+    let length = 12 + 8 + json.len() + bin_length;
+    writer.write_all(b"glTF")?;
+    writer.write_all(&2u32.to_le_bytes())?;
+    writer.write_all(&(length as u32).to_le_bytes())?;
+    writer.write_all(&(json.len() as u32).to_le_bytes())?;
+    writer.write_all(b"JSON")?;
+    writer.write_all(&json)?;
+    if !data.is_empty() {
+        writer.write_all(&(data.len() as u32).to_le_bytes())?;
+        writer.write_all(b"BIN\0")?;
+        writer.write_all(&data)?;
+    }
+    writer.flush()
+}
+
+#[cfg(all(test, feature = "gltf"))]
+mod tests {
+    use super::*;
+    use crate::mesh::triangle_list;
+    use crate::road::RoadBuilder;
+
+    fn material(texture: Option<&str>) -> GltfMaterial {
+        GltfMaterial {
+            base_color: Color::WHITE,
+            texture: texture.map(String::from),
+            perceptual_roughness: 0.5,
+            metallic: 0.0,
+            double_sided: false,
+        }
+    }
+
+    #[test]
+    fn exported_road_loads_back() {
+        let points = [
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::new(2.0, 0.0, 1.0),
+            Vec3::new(3.0, 0.0, 1.0),
+        ];
+        let road = RoadBuilder::new().detail(8).build(&points);
+        let parts = [
+            GltfPart {
+                name: "road".into(),
+                mesh: &road,
+                transform: Mat4::IDENTITY,
+                material: material(Some("road.png")),
+            },
+            GltfPart {
+                name: "copy".into(),
+                mesh: &road,
+                transform: Mat4::from_translation(Vec3::Y),
+                material: material(None),
+            },
+        ];
+        let path = std::env::temp_dir().join(format!("bezier_mesh_{}.glb", std::process::id()));
+        export_gltf(&path, &parts).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let gltf = gltf::Gltf::from_slice(&bytes).unwrap();
+        let blob = gltf.blob.as_deref();
+        let vertex_count = road.count_vertices();
+        let index_count = triangle_list(&road).unwrap().len();
+        assert_eq!(gltf.meshes().len(), parts.len());
+        for (mesh, part) in gltf.meshes().zip(&parts) {
+            assert_eq!(mesh.name(), Some(part.name.as_str()));
+            let primitives = mesh.primitives().collect::<Vec<_>>();
+            assert_eq!(primitives.len(), 1);
+            let primitive = &primitives[0];
+            for semantic in [
+                gltf::Semantic::Positions,
+                gltf::Semantic::Normals,
+                gltf::Semantic::TexCoords(0),
+            ] {
+                let accessor = primitive.get(&semantic).unwrap();
+                assert_eq!(accessor.count(), vertex_count, "{semantic:?}");
+            }
+            assert_eq!(primitive.indices().unwrap().count(), index_count);
+            // The data itself reads back out of the binary chunk
+            let reader = primitive.reader(|_| blob);
+            assert_eq!(reader.read_positions().unwrap().count(), vertex_count);
+
+            let texture = primitive
+                .material()
+                .pbr_metallic_roughness()
+                .base_color_texture()
+                .map(|info| info.texture().source().source());
+            match (texture, &part.material.texture) {
+                (Some(gltf::image::Source::Uri { uri, .. }), Some(expected)) => {
+                    assert_eq!(uri, expected)
+                }
+                (None, None) => {}
+                (texture, expected) => panic!("texture {texture:?}, expected {expected:?}"),
+            }
+        }
+    }
+}
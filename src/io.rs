@@ -0,0 +1,333 @@
+#[cfg(feature = "gltf")]
+use crate::RoadSurface;
+use crate::{
+    curve_points, modifier_held, spawn_control_points, Config, ControlPoint, History, MirrorOf,
+    RespawnControlPoints, Road,
+};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{EguiClipboard, EguiContexts};
+use bevy_panorbit_camera::PanOrbitCamera;
+use bezier_mesh::camera::CameraView;
+use bezier_mesh::export::export_centerline_csv;
+#[cfg(feature = "gltf")]
+use bezier_mesh::export::{export_gltf, GltfMaterial, GltfPart};
+use bezier_mesh::layout::read_layout;
+use bezier_mesh::share::{decode_design, encode_design};
+use itertools::Itertools;
+use std::time::SystemTime;
+
+/// How often the watched layout file is checked for changes, in seconds
+const WATCH_POLL_INTERVAL: f32 = 0.2;
+/// Seconds the watched layout file has to go unchanged before it's loaded
+const WATCH_DEBOUNCE: f32 = 0.5;
+
+/// State of the file named by `Config.watch_file`
+#[derive(Default)]
+pub struct WatchedFile {
+    path: Option<String>,
+    /// Modification time as of the last check
+    modified: Option<SystemTime>,
+    /// Time since startup when the file was last seen to change, until it's loaded
+    changed_at: Option<f32>,
+    poll_timer: Timer,
+}
+
+/// Reloads the roads from `Config.watch_file` whenever it changes on disk
+pub fn watch_layout_file(
+    time: Res<Time>,
+    config: Res<Config>,
+    mut road_q: Query<(Entity, &mut Road)>,
+    mut respawn_events: EventWriter<RespawnControlPoints>,
+    mut watched: Local<WatchedFile>,
+) {
+    let now = time.elapsed_seconds();
+    let modified_time = |path: &str| {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    if watched.path != config.watch_file {
+        *watched = WatchedFile {
+            path: config.watch_file.clone(),
+            modified: config.watch_file.as_deref().and_then(modified_time),
+            changed_at: Some(now - WATCH_DEBOUNCE),
+            poll_timer: Timer::from_seconds(WATCH_POLL_INTERVAL, TimerMode::Repeating),
+        };
+    }
+    let Some(path) = watched.path.clone() else {
+        return;
+    };
+    if watched.poll_timer.tick(time.delta()).just_finished() {
+        let modified = modified_time(&path);
+        if modified != watched.modified {
+            watched.modified = modified;
+            watched.changed_at = Some(now);
+        }
+    }
+    if !watched
+        .changed_at
+        .map_or(false, |changed_at| now - changed_at >= WATCH_DEBOUNCE)
+    {
+        return;
+    }
+    watched.changed_at = None;
+
+    let layouts = match read_layout(&path) {
+        Ok(layouts) => layouts,
+        Err(err) => {
+            error!("Failed to load {path}: {err}");
+            return;
+        }
+    };
+    let roads = road_q
+        .iter_mut()
+        .sorted_by_key(|(entity, _)| *entity)
+        .collect::<Vec<_>>();
+    if layouts.len() != roads.len() {
+        warn!(
+            "{path} has {} roads but the scene has {}, so only the first {} were loaded",
+            layouts.len(),
+            roads.len(),
+            layouts.len().min(roads.len())
+        );
+    }
+    let points_per_segment = config.curve_type.points_per_segment();
+    for ((entity, mut road), control_points) in roads.into_iter().zip(layouts) {
+        // Only whole segments can be built
+        if control_points.len() <= points_per_segment
+            || (control_points.len() - 1) % points_per_segment != 0
+        {
+            error!(
+                "A road in {path} has {} control points, which don't make whole segments",
+                control_points.len()
+            );
+            continue;
+        }
+        if road.control_points != control_points {
+            road.control_points = control_points;
+            respawn_events.send(RespawnControlPoints(entity));
+        }
+    }
+    info!("Loaded {path}");
+}
+
+/// Ctrl+Shift+C copies the config and every unmirrored road to the clipboard
+pub fn copy_design(
+    keys: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+    mut clipboard: ResMut<EguiClipboard>,
+    config: Res<Config>,
+    type_registry: Res<AppTypeRegistry>,
+    road_q: Query<(Entity, &Road), Without<MirrorOf>>,
+) {
+    if !keys.any_pressed([KeyCode::LControl, KeyCode::RControl])
+        || !keys.any_pressed([KeyCode::LShift, KeyCode::RShift])
+        || !keys.just_pressed(KeyCode::C)
+        || contexts.ctx_mut().wants_keyboard_input()
+    {
+        return;
+    }
+    let values = std::iter::once(&*config as &dyn Reflect)
+        .chain(
+            road_q
+                .iter()
+                .sorted_by_key(|(entity, _)| *entity)
+                .map(|(_, road)| road as &dyn Reflect),
+        )
+        .collect::<Vec<_>>();
+    match encode_design(&values, &type_registry.read()) {
+        Ok(design) => {
+            clipboard.set_contents(&design);
+            info!("Copied the design of {} roads", values.len() - 1);
+        }
+        Err(err) => error!("Failed to copy the design: {err}"),
+    }
+}
+
+/// Ctrl+Shift+V replaces the config and every road with a copied design
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn paste_design(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+    clipboard: Res<EguiClipboard>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut config: ResMut<Config>,
+    mut history: ResMut<History>,
+    type_registry: Res<AppTypeRegistry>,
+    road_q: Query<Entity, Or<(With<Road>, With<ControlPoint>)>>,
+) {
+    if !keys.any_pressed([KeyCode::LControl, KeyCode::RControl])
+        || !keys.any_pressed([KeyCode::LShift, KeyCode::RShift])
+        || !keys.just_pressed(KeyCode::V)
+        || contexts.ctx_mut().wants_keyboard_input()
+    {
+        return;
+    }
+    let Some(design) = clipboard.get_contents() else {
+        error!("Failed to paste a design: the clipboard has no text");
+        return;
+    };
+    let values = match decode_design(&design, &type_registry.read()) {
+        Ok(values) => values,
+        Err(err) => {
+            error!("Failed to paste a design: {err}");
+            return;
+        }
+    };
+    // Checked up front, so a mismatched design doesn't leave the scene half replaced
+    let is = |value: &dyn Reflect, type_name| value.type_name() == type_name;
+    let Some((pasted_config, pasted_roads)) = values
+        .split_first()
+        .filter(|(first, _)| is(&***first, std::any::type_name::<Config>()))
+        .filter(|(_, roads)| {
+            roads
+                .iter()
+                .all(|road| is(&**road, std::any::type_name::<Road>()))
+        })
+    else {
+        error!("Failed to paste a design: it doesn't hold a config followed by roads");
+        return;
+    };
+
+    config.apply(&**pasted_config);
+    // Surfaces, guardrails and followers are children of their road, so they go with it
+    for entity in road_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for pasted_road in pasted_roads {
+        let mut road = Road::default();
+        road.apply(&**pasted_road);
+        let control_points = road.control_points.clone();
+        let road = commands.spawn((road, SpatialBundle::default())).id();
+        spawn_control_points(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            road,
+            &control_points,
+            &config,
+        );
+    }
+    *history = History::default();
+    info!("Pasted the design of {} roads", pasted_roads.len());
+}
+
+/// Where the camera's view is kept between sessions, relative to the working directory
+pub const CAMERA_VIEW_FILE: &str = "camera.txt";
+
+/// Saves the camera's view to `CAMERA_VIEW_FILE` on exit or Ctrl+S
+pub fn save_camera(
+    keys: Res<Input<KeyCode>>,
+    mut exit_events: EventReader<AppExit>,
+    camera_q: Query<&PanOrbitCamera>,
+) {
+    let exiting = exit_events.iter().count() > 0;
+    let saving =
+        keys.any_pressed([KeyCode::LControl, KeyCode::RControl]) && keys.just_pressed(KeyCode::S);
+    if !exiting && !saving {
+        return;
+    }
+    for camera in camera_q.iter() {
+        let view = CameraView {
+            focus: camera.focus,
+            radius: camera.radius,
+            alpha: camera.alpha,
+            beta: camera.beta,
+        };
+        match view.save(CAMERA_VIEW_FILE) {
+            Ok(()) => info!("Saved camera to {CAMERA_VIEW_FILE}"),
+            Err(err) => error!("Failed to save camera to {CAMERA_VIEW_FILE}: {err}"),
+        }
+    }
+}
+
+/// Pressing C writes each road's centerline to `centerline_<n>.csv`
+pub fn export_centerlines(
+    keys: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+    config: Res<Config>,
+    road_q: Query<(Entity, &Road)>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+) {
+    if !keys.just_pressed(KeyCode::C)
+        || modifier_held(&keys)
+        || contexts.ctx_mut().wants_keyboard_input()
+    {
+        return;
+    }
+    for (i, (entity, road)) in road_q
+        .iter()
+        .sorted_by_key(|(entity, _)| *entity)
+        .enumerate()
+    {
+        let path = format!("centerline_{}.csv", i + 1);
+        let samples = config.centerline_samples.unwrap_or(road.detail);
+        match export_centerline_csv(&curve_points(&point_q, entity, &config), &path, samples) {
+            Ok(()) => info!("Exported centerline to {path}"),
+            Err(err) => error!("Failed to export centerline to {path}: {err}"),
+        }
+    }
+}
+
+/// Pressing G writes each road to `road_<n>.glb`, referencing its textures
+#[cfg(feature = "gltf")]
+#[allow(clippy::type_complexity)]
+pub fn export_roads_gltf(
+    keys: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+    meshes: Res<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+    road_q: Query<Entity, With<Road>>,
+    surface_q: Query<(
+        &RoadSurface,
+        &Handle<Mesh>,
+        Option<&Handle<StandardMaterial>>,
+        &GlobalTransform,
+        &Parent,
+    )>,
+) {
+    if !keys.just_pressed(KeyCode::G)
+        || modifier_held(&keys)
+        || contexts.ctx_mut().wants_keyboard_input()
+    {
+        return;
+    }
+    for (i, road) in road_q.iter().sorted().enumerate() {
+        let parts = surface_q
+            .iter()
+            .filter(|(.., parent)| parent.get() == road)
+            .sorted_by_key(|(surface, ..)| (surface.group as usize, surface.texture.clone()))
+            .filter_map(|(surface, mesh, material, transform, _)| {
+                let material = material.and_then(|handle| materials.get(handle));
+                let name = match &surface.texture {
+                    Some(texture) => format!("{:?} ({texture})", surface.group),
+                    None => format!("{:?}", surface.group),
+                };
+                Some(GltfPart {
+                    name,
+                    mesh: meshes.get(mesh)?,
+                    transform: transform.compute_matrix(),
+                    material: GltfMaterial {
+                        base_color: material.map_or(Color::WHITE, |m| m.base_color),
+                        texture: material
+                            .and_then(|m| m.base_color_texture.as_ref())
+                            .and_then(|texture| asset_server.get_handle_path(texture))
+                            .map(|path| format!("assets/{}", path.path().display())),
+                        perceptual_roughness: material.map_or(0.5, |m| m.perceptual_roughness),
+                        metallic: material.map_or(0.0, |m| m.metallic),
+                        double_sided: material.map_or(false, |m| m.double_sided),
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        let path = format!("road_{}.glb", i + 1);
+        match export_gltf(&path, &parts) {
+            Ok(()) => info!("Exported road to {path}"),
+            Err(err) => error!("Failed to export road to {path}: {err}"),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Reads control point layouts written by an external tool. See [`parse_layout`]
+pub fn read_layout(path: impl AsRef<Path>) -> io::Result<Vec<Vec<Vec3>>> {
+    parse_layout(&fs::read_to_string(path)?)
+}
+
+/// Control points of each road in a layout, one `x y z` point per line with the numbers separated
+/// by spaces or commas. Blank lines separate one road from the next, and lines starting with `#`
+/// are ignored
+pub fn parse_layout(text: &str) -> io::Result<Vec<Vec<Vec3>>> {
+    let mut roads = vec![];
+    let mut road = vec![];
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.is_empty() {
+            if !road.is_empty() {
+                roads.push(std::mem::take(&mut road));
+            }
+            continue;
+        }
+        let numbers = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|number| !number.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<f32>, _>>()
+            .ok()
+            .filter(|numbers| numbers.len() == 3)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("expected three numbers for a point, found '{line}'"),
+                )
+            })?;
+        road.push(Vec3::from_slice(&numbers));
+    }
+    if !road.is_empty() {
+        roads.push(road);
+    }
+    Ok(roads)
+}
@@ -0,0 +1,11 @@
+//! Curve evaluation and sampling for extruding meshes along Bézier curves
+
+pub mod camera;
+pub mod curve;
+pub mod export;
+pub mod layout;
+pub mod mesh;
+pub mod profile;
+pub mod road;
+pub mod share;
+pub mod svg;
@@ -1,79 +1,688 @@
+use crate::decorations::{
+    swap_lods, update_crosswalks, update_footprints, update_guardrails, update_lane_markings,
+    update_lods, update_pillars,
+};
+#[cfg(feature = "gltf")]
+use crate::io::export_roads_gltf;
+use crate::io::{
+    copy_design, export_centerlines, paste_design, save_camera, watch_layout_file, CAMERA_VIEW_FILE,
+};
 use crate::material::UvDebugMaterial;
+use bevy::diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin};
+use bevy::pbr::wireframe::{Wireframe, WireframePlugin};
 use bevy::prelude::*;
-use bevy::render::mesh::{Indices, PrimitiveTopology};
+#[cfg(feature = "rapier")]
+use bevy::render::mesh::Indices;
+use bevy::render::mesh::{PrimitiveTopology, VertexAttributeValues};
+use bevy::render::primitives::Aabb;
+use bevy::render::render_resource::{AddressMode, SamplerDescriptor, WgpuFeatures};
+use bevy::render::settings::WgpuSettings;
+use bevy::render::texture::ImageSampler;
+use bevy::render::RenderPlugin;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
 use bevy_inspector_egui::prelude::*;
-use bevy_inspector_egui::quick::{ResourceInspectorPlugin, WorldInspectorPlugin};
-use bevy_mod_picking::{DefaultPickingPlugins, PickableBundle, PickingCameraBundle};
+use bevy_inspector_egui::quick::{
+    AssetInspectorPlugin, ResourceInspectorPlugin, WorldInspectorPlugin,
+};
+use bevy_inspector_egui::reflect_inspector::ui_for_value_readonly;
+use bevy_mod_picking::{
+    DefaultPickingPlugins, PickableBundle, PickingCamera, PickingCameraBundle, Selection,
+};
+use bevy_mod_raycast::{ray_intersection_over_mesh, Backfaces, Ray3d};
 use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
-use bevy_transform_gizmo::{GizmoPickSource, GizmoTransformable, TransformGizmoPlugin};
+#[cfg(feature = "rapier")]
+use bevy_rapier3d::prelude::{Collider, ComputedColliderShape, NoUserData, RapierPhysicsPlugin};
+use bevy_transform_gizmo::{
+    GizmoPickSource, GizmoTransformable, TransformGizmo, TransformGizmoEvent, TransformGizmoPlugin,
+};
 use bevy_vector_shapes::prelude::*;
+use bezier_mesh::camera::CameraView;
+use bezier_mesh::curve::{
+    cardinal_chain, closed_chain, closed_quadratic_chain, cubic_bezier, cubic_bezier_curvature,
+    cubic_bezier_curvature_vector, cubic_bezier_direction, curve_frames, curve_length,
+    extrapolate_segment, insert_anchor, insert_quadratic_anchor, is_cubic_chain, join_chains,
+    nearest_to_ray, quadratic_chain, segments, subdivision_parameters, ArcLengthTable, FrameMode,
+};
+#[cfg(feature = "rapier")]
+use bezier_mesh::mesh::strip_to_list;
+use bezier_mesh::mesh::{triangle_count, triangle_list, MeshData};
+use bezier_mesh::profile::{
+    apply_atlas, atlas_ranges, cap_ring_count, cap_rings, profile_edges, profile_is_closed,
+    profile_surfaces, profile_top, profile_vertices, slice_triangles, surface_vertices, CapStyle,
+    ProfileKind, SurfaceGroup, UvLayout, Vertex, ROAD_SURFACE_HEIGHT,
+};
+use bezier_mesh::svg::import_svg;
 use itertools::Itertools;
-use std::f32::consts::{FRAC_1_SQRT_2, TAU};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+use std::ops::Range;
 
+mod decorations;
+mod io;
 mod material;
 
 fn main() {
-    App::new()
-        .insert_resource(Config {
-            detail: 20,
-            control_points: (0..4)
-                .map(|i| Vec3::new(i as f32 * 3.0, 0.0, 0.0))
-                .collect(),
-            auto_update: true,
+    let mut app = App::new();
+    app.insert_resource(Config {
+        auto_update: true,
+        texture_tiling: 1.0,
+        up: Vec3::Y,
+        normal_line_thickness: 0.005,
+        normal_line_length: 0.15,
+        max_chord_error: 0.005,
+        min_slices: 4,
+        max_slices: 150,
+        lanes: 1,
+        frame_stride: 4,
+        profile_scale: Vec2::ONE,
+        grid: Some(Grid {
+            cell_size: 1.0,
+            extent: 10.0,
+        }),
+        nudge_step: 0.1,
+        enforce_g1: true,
+        point_radius: 0.05,
+        anchor_color: Color::RED,
+        handle_color: Color::ORANGE,
+        svg_file: "road.svg".into(),
+        svg_scale: 0.01,
+        terrain_clearance: 0.02,
+        curb_color: Color::SILVER,
+        lod_distance: 20.0,
+        underside_color: Color::DARK_GRAY,
+        ..default()
+    })
+    .register_type::<Config>()
+    .register_type::<RoadStats>()
+    .init_resource::<RoadStats>()
+    .register_type::<Road>()
+    // Everything a shared design holds has to be registered for it to be read back
+    .register_type::<ProfileKind>()
+    .register_type::<CapStyle>()
+    .register_type::<UvLayout>()
+    .register_type::<FrameMode>()
+    .register_type::<CurveType>()
+    .register_type::<MaterialMode>()
+    .register_type::<Topology>()
+    .register_type::<NormalMode>()
+    .register_type::<DensityMode>()
+    .register_type::<TextureWrap>()
+    .register_type::<Option<MirrorAxis>>()
+    .register_type::<Option<Grid>>()
+    .register_type::<Option<Pillars>>()
+    .register_type::<Option<LaneMarkings>>()
+    .register_type::<Option<usize>>()
+    .register_type::<Option<f32>>()
+    .register_type::<Vec<WidthKeyframe>>()
+    .register_type::<Vec<ElevationKeyframe>>()
+    .register_type::<Vec<f32>>()
+    .register_type::<Vec<Vec2>>()
+    .register_type::<Vec<Vec3>>()
+    .register_type::<Vec<Option<String>>>()
+    .register_type::<PathFollower>()
+    .add_plugins(
+        DefaultPlugins
+            .set(ImagePlugin {
+                // Road texture V runs along the curve and is tiled, so it needs to repeat
+                default_sampler: SamplerDescriptor {
+                    address_mode_u: AddressMode::Repeat,
+                    address_mode_v: AddressMode::Repeat,
+                    ..ImageSampler::linear_descriptor()
+                },
+            })
+            .set(RenderPlugin {
+                // Needed by the wireframe material mode
+                wgpu_settings: WgpuSettings {
+                    features: WgpuFeatures::POLYGON_MODE_LINE,
+                    ..default()
+                },
+            }),
+    )
+    .add_plugin(WireframePlugin)
+    .add_plugin(FrameTimeDiagnosticsPlugin)
+    .add_plugin(PanOrbitCameraPlugin)
+    .add_plugin(ShapePlugin {
+        base_config: ShapeConfig {
+            alignment: Alignment::Billboard,
             ..default()
-        })
-        .register_type::<Config>()
-        .add_plugins(DefaultPlugins)
-        .add_plugin(PanOrbitCameraPlugin)
-        .add_plugin(ShapePlugin {
-            base_config: ShapeConfig {
-                alignment: Alignment::Billboard,
-                ..default()
-            },
-        })
-        .add_plugin(ResourceInspectorPlugin::<Config>::default())
-        .add_plugin(WorldInspectorPlugin::default())
-        .add_plugins(DefaultPickingPlugins)
-        .add_plugin(TransformGizmoPlugin::default())
-        .add_plugin(MaterialPlugin::<UvDebugMaterial>::default())
-        .add_startup_system(setup)
-        .add_systems((build_mesh.run_if(|config: Res<Config>| config.auto_update),).chain())
-        .run()
+        },
+    })
+    .add_plugin(ResourceInspectorPlugin::<Config>::default())
+    .add_plugin(WorldInspectorPlugin::default())
+    .add_plugins(DefaultPickingPlugins)
+    .add_plugin(TransformGizmoPlugin::default())
+    .add_plugin(MaterialPlugin::<UvDebugMaterial>::default())
+    .register_asset_reflect::<UvDebugMaterial>()
+    .add_plugin(AssetInspectorPlugin::<UvDebugMaterial>::default())
+    .add_startup_system(setup)
+    .add_systems(
+        (
+            drift_control_points,
+            nudge_control_points,
+            snap_control_points,
+            constrain_handles,
+            store_dragged_points,
+            sync_mirrors,
+            mark_generation_dirty,
+            start_generation,
+            build_mesh.run_if(resource_equals(GenerationState::Building)),
+            finish_generation,
+        )
+            .chain(),
+    )
+    .init_resource::<GenerationState>()
+    .add_event::<RespawnControlPoints>()
+    .add_event::<PointsNudged>()
+    .add_event::<RebuildMeshes>()
+    .add_system(draw_handles)
+    .add_system(draw_overlay)
+    .add_system(draw_frames)
+    .add_system(draw_measurements)
+    .add_system(draw_point_labels)
+    .add_system(check_overlaps)
+    .add_system(draw_grid)
+    .add_system(draw_normals.after(build_mesh))
+    .add_system(draw_wireframe_overlay.after(build_mesh))
+    .init_resource::<History>()
+    .add_systems(
+        (
+            segment_panel,
+            insert_control_point,
+            undo_redo,
+            reset_roads,
+            respawn_control_points,
+        )
+            .chain(),
+    )
+    .add_system(
+        record_history
+            .after(respawn_control_points)
+            .after(store_dragged_points),
+    )
+    .add_system(follow_path)
+    .add_system(restyle_control_points.before(respawn_control_points))
+    .add_system(scale_control_points)
+    .add_system(update_guardrails.after(build_mesh))
+    .add_system(update_pillars.after(build_mesh))
+    .add_system(update_lane_markings.after(build_mesh))
+    .add_system(update_crosswalks.after(build_mesh))
+    .add_system(update_footprints.after(build_mesh))
+    .add_system(update_lods.after(build_mesh))
+    .add_system(swap_lods.after(update_lods))
+    .add_system(update_material.after(build_mesh))
+    .add_system(apply_texture_wrap.after(update_material))
+    .add_system(frame_roads)
+    .add_system(snap_camera_view)
+    .add_system(stats_panel.after(build_mesh))
+    .add_system(export_centerlines)
+    .add_system(copy_design)
+    .add_system(paste_design)
+    .add_system(watch_layout_file)
+    // Last, so it sees the exit sent when the window is closed
+    .add_system(save_camera.in_base_set(CoreSet::Last));
+    #[cfg(feature = "rapier")]
+    app.add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+        .add_system(update_collider.after(build_mesh));
+    #[cfg(feature = "gltf")]
+    app.add_system(export_roads_gltf.after(update_material));
+    app.run()
 }
 
-#[derive(Component, Default, Debug)]
-struct ControlPoint(usize);
+/// One road in the scene, which becomes `Generated` once its surface meshes have been built
+#[derive(Component, Reflect, Default, Debug, InspectorOptions)]
+#[reflect(Component, InspectorOptions)]
+struct Road {
+    /// Layout of the road as of the last edit, which its `ControlPoint` spheres are spawned from
+    control_points: Vec<Vec3>,
+    #[inspector(min = 2, max = 150)]
+    detail: usize,
+    /// Driving surface texture of each segment, or `None` for `Config.texture_path`
+    segment_textures: Vec<Option<String>>,
+}
+
+#[derive(Component, Debug)]
+struct ControlPoint {
+    /// `Road` entity the point belongs to
+    road: Entity,
+    index: usize,
+}
 
 #[derive(Component, Default, Debug)]
 struct Generated;
 
+/// One surface of a generated road, split further by texture on the driving surface
+#[derive(Component, Debug)]
+struct RoadSurface {
+    group: SurfaceGroup,
+    /// Texture from `Road.segment_textures` of the segments this covers, or `None` for the default
+    texture: Option<String>,
+}
+
+/// Ground that roads are dropped onto when `Config.conform_to_terrain` is on
+#[derive(Component, Default, Debug)]
+struct Terrain;
+
+/// On a `Generated` road whose surfaces' colliders are up to date with their meshes
+#[cfg(feature = "rapier")]
+#[derive(Component, Default, Debug)]
+struct ColliderBuilt;
+
+/// Road kept as the reflection of another `Road` across `Config.mirror_plane`
+#[derive(Component, Debug)]
+struct MirrorOf(Entity);
+
+/// Sent after a `Road`'s control points are replaced, to respawn its `ControlPoint` spheres
+struct RespawnControlPoints(Entity);
+
+/// Sent after selected control points are moved with the keyboard
+struct PointsNudged;
+
+/// Sent to run `build_mesh` once while `Config.auto_update` is off
+struct RebuildMeshes;
+
+/// Whether the roads' meshes are up to date with their control points and the config
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq)]
+enum GenerationState {
+    /// Nothing has changed since the last build
+    #[default]
+    Idle,
+    /// Something has changed, and is waiting for auto update or a `RebuildMeshes` to be built
+    Dirty,
+    /// `build_mesh` runs this frame
+    Building,
+}
+
+/// Most edits kept for undoing. The oldest are dropped beyond this
+const MAX_UNDO_DEPTH: usize = 100;
+
+/// Control point layouts of every road, keyed by `Road` entity
+type Layouts = HashMap<Entity, Vec<Vec3>>;
+
+/// Layouts from before each edit, for undo and redo
+#[derive(Resource, Default)]
+struct History {
+    undo: VecDeque<Layouts>,
+    redo: Vec<Layouts>,
+    /// Layouts as of the last recorded edit, to compare against for the next one
+    current: Layouts,
+}
+
+/// Drives an entity along its parent `Road` at a constant speed
 #[derive(Component, Default, Debug, Reflect)]
 #[reflect(Component)]
-struct Velocity(Vec2);
+struct PathFollower {
+    /// Current arc-length distance from the start of the curve
+    distance: f32,
+    /// Units per second. Negative speeds travel back towards the start
+    speed: f32,
+}
+
+/// Totals across every generated road, shown in the stats panel
+#[derive(Reflect, Resource, Default, Clone, Copy, Debug, PartialEq, InspectorOptions)]
+#[reflect(Resource, InspectorOptions)]
+struct RoadStats {
+    roads: usize,
+    /// Length along the generated slices, with elevation and terrain
+    curve_length: f32,
+    segments: usize,
+    vertices: usize,
+    /// Not counting the degenerate triangles that join strips
+    triangles: usize,
+    /// Corners of the box around every road's mesh, or zero with no roads
+    bounds_min: Vec3,
+    bounds_max: Vec3,
+}
+
+impl RoadStats {
+    /// Totals of `self` and `other` together, with bounds around both
+    fn merge(self, other: RoadStats) -> RoadStats {
+        let (bounds_min, bounds_max) = match (self.roads, other.roads) {
+            (_, 0) => (self.bounds_min, self.bounds_max),
+            (0, _) => (other.bounds_min, other.bounds_max),
+            _ => (
+                self.bounds_min.min(other.bounds_min),
+                self.bounds_max.max(other.bounds_max),
+            ),
+        };
+        RoadStats {
+            roads: self.roads + other.roads,
+            curve_length: self.curve_length + other.curve_length,
+            segments: self.segments + other.segments,
+            vertices: self.vertices + other.vertices,
+            triangles: self.triangles + other.triangles,
+            bounds_min,
+            bounds_max,
+        }
+    }
+}
 
 #[derive(Reflect, Resource, Default, InspectorOptions)]
 #[reflect(Resource, InspectorOptions)]
 struct Config {
     auto_update: bool,
-    #[inspector(min = 2, max = 150)]
-    detail: usize,
-    control_points: Vec<Vec3>,
-    mesh: Option<Handle<Mesh>>,
+    /// Slices per segment while dragging with the gizmo, or `None` to always use full detail
+    preview_detail: Option<usize>,
+    /// How many times the road texture repeats per unit of length along the curve
+    texture_tiling: f32,
+    /// How U is laid out around the profile. The atlas ranges are listed in the stats panel
+    uv_layout: UvLayout,
+    /// How the road texture is sampled past its edges across the road (U)
+    texture_wrap_u: TextureWrap,
+    /// How the road texture is sampled past its edges along the road (V)
+    texture_wrap_v: TextureWrap,
+    /// How many slices each segment gets when `adaptive` is off
+    density_mode: DensityMode,
+    /// Subdivide segments to within `max_chord_error` instead of slicing them evenly
+    adaptive: bool,
+    /// Furthest adaptive slicing lets the mesh stray from the curve, in world units
+    #[inspector(min = 0.0001)]
+    max_chord_error: f32,
+    /// Fewest slices per segment in adaptive mode
+    #[inspector(min = 2)]
+    min_slices: usize,
+    /// Most slices per segment in adaptive mode
+    #[inspector(min = 2, max = 500)]
+    max_slices: usize,
+    /// Draw the generated mesh's vertex normals
+    debug_normals: bool,
+    /// Draw every triangle edge of the generated mesh over the shaded road. Slow on dense meshes
+    show_wireframe_overlay: bool,
+    /// Draw the sampled centerline, coloured by `t` along each segment, and the control polygon
+    show_overlay: bool,
+    /// Label the straight-line distance between consecutive control points
+    show_measurements: bool,
+    /// With `show_measurements`, also label each anchor with the length of the curve up to it
+    show_arc_lengths: bool,
+    /// Label each control point with its index along its road
+    show_point_labels: bool,
+    /// Warn when a road's centerline comes back this close to itself. `None` to skip
+    overlap_distance: Option<f32>,
+    /// With `overlap_distance`, draw lines across the gap wherever a road comes too close to itself
+    show_overlaps: bool,
+    /// Draw each slice's right, up and forward axes in red, green and blue
+    show_frames: bool,
+    /// Slices between each frame drawn with `show_frames`
+    #[inspector(min = 1)]
+    frame_stride: usize,
+    normal_line_thickness: f32,
+    normal_line_length: f32,
+    profile_kind: ProfileKind,
+    /// Number of lanes across the road surface, each taking one repeat of the road texture
+    #[inspector(min = 1)]
+    lanes: usize,
+    /// Stretch of the profile across (X) and up (Y), on top of `width_profile`
+    profile_scale: Vec2,
+    /// Shift of the profile to the right of the curve, after it's stretched
+    lateral_offset: f32,
+    /// Width of the road along its length. Empty for the profile's own width throughout
+    width_profile: Vec<WidthKeyframe>,
+    /// Height added along the road, on top of the control points' own
+    elevation: Vec<ElevationKeyframe>,
+    /// Index layout of the generated mesh
+    topology: Topology,
+    /// Where the generated mesh's vertex normals come from
+    normal_mode: NormalMode,
+    /// Reverse the winding and normals of the generated faces, for a road that comes out inside out
+    flip_faces: bool,
+    /// Merge duplicate vertices into a shared, indexed buffer
+    weld: bool,
+    /// Use 16 bit indices for surfaces with few enough vertices
+    prefer_u16: bool,
+    /// Reference up direction for the road's cross-section
+    up: Vec3,
+    /// How the cross-section is turned about the curve at each slice
+    frame_mode: FrameMode,
+    /// Distance either side of a join over which a kink in the frames is eased out
+    smooth_joins: f32,
+    /// How strongly the road banks into bends, as radians of roll per unit of curvature
+    auto_bank: f32,
+    /// Full turns the cross-section spins through from one end of the road to the other
+    twist_turns: f32,
+    /// Drop each slice onto the `Terrain` below it instead of following the height of the curve
+    conform_to_terrain: bool,
+    /// Height the road sits above the terrain when conforming
+    terrain_clearance: f32,
+    /// Tilt the road to follow the slope of the terrain when conforming, in place of banking
+    align_to_terrain: bool,
+    /// How the control points define the curve
+    curve_type: CurveType,
+    /// Catmull-Rom only: 0 for a standard spline, up to 1 for straight lines
+    #[inspector(min = 0.0, max = 1.0)]
+    tension: f32,
+    /// Catmull-Rom only: space knots by the square root of the waypoint distance
+    centripetal: bool,
+    /// Spacing along the road between guardrail posts, or `None` for no posts
+    guardrails: Option<f32>,
+    /// Columns under the road, or `None` for no columns
+    pillars: Option<Pillars>,
+    /// Dashed lines down the middle of the road and between its lanes, or `None` for no markings
+    lane_markings: Option<LaneMarkings>,
+    /// Distances along the road at which a crosswalk is painted
+    crosswalks: Vec<f32>,
+    /// Slices per segment of a low detail copy of each road, or `None` for no copy
+    generate_lod: Option<usize>,
+    /// Distance from the camera beyond which a road's low detail copy is drawn
+    lod_distance: f32,
+    /// Shade the ground under each road with its outline
+    generate_footprint: bool,
+    /// Reference grid drawn on the Y = 0 plane, or `None` for no grid
+    grid: Option<Grid>,
+    /// Join the last control point back to the first with an extra segment
+    closed: bool,
+    /// How the ends of an open road are closed off
+    cap_style: CapStyle,
+    /// Keep a mirrored copy of every road across this plane
+    mirror_plane: Option<MirrorAxis>,
+    /// Grid spacing dragged control points snap to, or `None` to place them freely
+    grid_snap: Option<f32>,
+    /// Keep the handles around each interior anchor pointing in opposite directions
+    enforce_g1: bool,
+    /// With `enforce_g1`, keep the opposite handle's length instead of mirroring it
+    preserve_handle_lengths: bool,
+    /// Clicking on the curve splits it, inserting a new anchor under the cursor
+    insert_mode: bool,
+    /// How far the arrow keys move the selected control points, ten times with Shift
+    nudge_step: f32,
+    #[inspector(min = 0.001)]
+    point_radius: f32,
+    /// Colour of the control points on the curve
+    anchor_color: Color,
+    /// Colour of the control points that pull the curve towards them
+    handle_color: Color,
+    /// Grow the control point spheres with distance from the camera
+    scale_points_with_distance: bool,
+    /// Swing the camera round to the preset views instead of cutting straight to them
+    animate_views: bool,
+    /// How the roads are shaded
+    material_mode: MaterialMode,
+    /// Road surface texture relative to the assets folder, or `None` for the default
+    texture_path: Option<String>,
+    /// Colour the road by signed curvature instead of texturing it
+    curvature_coloring: bool,
+    /// Bake ambient occlusion into the vertex colours. See `ring_occlusion`
+    bake_ao: bool,
+    /// Draw and light the back of every face too
+    double_sided: bool,
+    /// Colour of the raised curbs either side of the road surface
+    curb_color: Color,
+    /// Colour of the road's outer walls and bottom
+    underside_color: Color,
+    /// SVG document whose first path is imported as a road's control points
+    svg_file: String,
+    /// World units per SVG user unit. SVG X maps to world X and SVG Y to world Z
+    svg_scale: f32,
+    /// Layout file to reload the roads from whenever it changes, or `None`
+    watch_file: Option<String>,
+    /// Points written for each road by the centerline export, or `None` for the road's `detail`
+    centerline_samples: Option<usize>,
+    /// Keep the control points drifting about and the roads rebuilding, for an idle demo
+    demo_mode: bool,
+    #[cfg(feature = "rapier")]
+    generate_collider: bool,
+    /// Slices per segment of the collider, or `None` to use the render mesh
+    #[cfg(feature = "rapier")]
+    collider_detail: Option<usize>,
 }
 
-#[derive(Default)]
-struct Vertex {
-    point: Vec3,
-    normal: Vec3,
-    uv: Vec2,
+/// How the control points are interpreted
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, Default, PartialEq)]
+enum CurveType {
+    /// Segments of four points sharing their end points, with tangent handles in between
+    #[default]
+    Bezier,
+    /// Every control point is a waypoint the curve passes through
+    CatmullRom,
+    /// Segments of three points sharing their end points, with a single handle in between
+    Quadratic,
+}
+
+impl CurveType {
+    /// Control points each segment adds beyond the one it shares with the last
+    fn points_per_segment(self) -> usize {
+        match self {
+            CurveType::Bezier => 3,
+            CurveType::CatmullRom => 1,
+            CurveType::Quadratic => 2,
+        }
+    }
+
+    /// Whether the control point at `index` lies on the curve, rather than being a handle
+    fn is_anchor(self, index: usize) -> bool {
+        index % self.points_per_segment() == 0
+    }
+}
+
+/// Material the roads are drawn with
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, Default, PartialEq)]
+enum MaterialMode {
+    /// Road texture, or vertex colours with `curvature_coloring`
+    #[default]
+    Textured,
+    /// UV grid, which can be tuned in the debug material's asset inspector
+    UvDebug,
+    /// Textured with the triangle edges drawn over the top
+    Wireframe,
+}
+
+/// How a texture is sampled outside the 0 to 1 range of its coordinates
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, Default, PartialEq)]
+enum TextureWrap {
+    #[default]
+    Repeat,
+    /// Stretch the edge texels out
+    Clamp,
+    /// Tile the texture, flipping every other copy
+    Mirror,
+}
+
+impl TextureWrap {
+    fn address_mode(self) -> AddressMode {
+        match self {
+            TextureWrap::Repeat => AddressMode::Repeat,
+            TextureWrap::Clamp => AddressMode::ClampToEdge,
+            TextureWrap::Mirror => AddressMode::MirrorRepeat,
+        }
+    }
+}
+
+/// How the generated mesh's indices are laid out
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, Default, PartialEq)]
+enum Topology {
+    #[default]
+    TriangleList,
+    /// Fewer indices for the same triangles, one strip per face along the road
+    TriangleStrip,
+}
+
+/// How many slices each segment is cut into, spaced evenly along it
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, Default, PartialEq)]
+enum DensityMode {
+    /// Each road's own `detail` to a segment, however long the segment is
+    #[default]
+    Fixed,
+    /// This many spans per unit of length, with at least one to a segment
+    PerLength(f32),
+}
+
+/// Source of the generated mesh's vertex normals
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, Default, PartialEq)]
+enum NormalMode {
+    /// The profile's own normals, smooth across rounded profiles
+    #[default]
+    Authored,
+    /// Each triangle's face normal, with unshared vertices in a triangle list
+    FlatPerTriangle,
+}
+
+/// Plane through the origin that roads are mirrored across, named by the axis it's perpendicular to
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, Default, PartialEq)]
+enum MirrorAxis {
+    /// The X = 0 plane
+    #[default]
+    X,
+    /// The Y = 0 plane
+    Y,
+    /// The Z = 0 plane
+    Z,
 }
 
-impl Vertex {
-    fn new(point: Vec3, normal: Vec3, uv: Vec2) -> Self {
-        Vertex { point, normal, uv }
+impl MirrorAxis {
+    fn reflect(self, point: Vec3) -> Vec3 {
+        match self {
+            MirrorAxis::X => Vec3::new(-point.x, point.y, point.z),
+            MirrorAxis::Y => Vec3::new(point.x, -point.y, point.z),
+            MirrorAxis::Z => Vec3::new(point.x, point.y, -point.z),
+        }
     }
 }
 
+/// Columns standing under the road wherever it's raised off the ground
+#[derive(Reflect, FromReflect, Clone, Debug, Default)]
+struct Pillars {
+    /// Distance along the road between columns
+    spacing: f32,
+    /// Height of the ground the columns stand on
+    ground: f32,
+}
+
+/// Dashed lines painted along the road surface
+#[derive(Reflect, FromReflect, Clone, Debug, Default)]
+struct LaneMarkings {
+    /// Width of each line across the road
+    width: f32,
+    dash_length: f32,
+    /// Length of the gap between dashes, or 0 for solid lines
+    gap_length: f32,
+}
+
+/// Lines on the Y = 0 plane for judging scale and height by
+#[derive(Reflect, FromReflect, Clone, Debug, Default)]
+struct Grid {
+    /// Distance between neighbouring lines
+    cell_size: f32,
+    /// How far the grid reaches from the origin along X and Z
+    extent: f32,
+}
+
+/// Width of the road at a point along it, relative to the profile as authored
+#[derive(Reflect, FromReflect, Clone, Debug, Default)]
+struct WidthKeyframe {
+    /// Fraction of the way along the road, from 0 at the start to 1 at the end
+    position: f32,
+    width: f32,
+}
+
+/// Height added to the road at a point along it, along `Config.up`
+#[derive(Reflect, FromReflect, Clone, Debug, Default)]
+struct ElevationKeyframe {
+    /// Fraction of the way along the road, from 0 at the start to 1 at the end
+    position: f32,
+    height: f32,
+}
+
 fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -90,30 +699,158 @@ fn setup(
         transform: Transform::from_xyz(4.0, 8.0, 4.0),
         ..default()
     });
-    // Camera
+    // Camera, looking from wherever it was left last session
+    let mut camera = PanOrbitCamera {
+        radius: 10.0,
+        button_orbit: MouseButton::Middle,
+        modifier_pan: Some(KeyCode::LShift),
+        button_pan: MouseButton::Middle,
+        ..default()
+    };
+    match CameraView::load(CAMERA_VIEW_FILE) {
+        Ok(view) => {
+            camera.focus = view.focus;
+            camera.radius = view.radius;
+            camera.alpha = view.alpha;
+            camera.beta = view.beta;
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => warn!("Failed to restore camera from {CAMERA_VIEW_FILE}: {err}"),
+    }
     commands.spawn((
         Camera3dBundle::default(),
-        PanOrbitCamera {
-            radius: 10.0,
-            button_orbit: MouseButton::Middle,
-            modifier_pan: Some(KeyCode::LShift),
-            button_pan: MouseButton::Middle,
-            ..default()
-        },
+        camera,
         PickingCameraBundle::default(),
         GizmoPickSource::default(),
     ));
 
-    // Control point meshes
-    for (i, point) in config.control_points.iter().enumerate() {
+    // Rolling ground under the roads, for them to be conformed to
+    commands.spawn((
+        Terrain,
+        PbrBundle {
+            mesh: meshes.add(terrain_mesh(TERRAIN_SIZE, TERRAIN_RESOLUTION)),
+            material: materials.add(Color::DARK_GREEN.into()),
+            transform: Transform::from_xyz(4.5, -0.5, -1.5),
+            ..default()
+        },
+    ));
+
+    spawn_starting_roads(&mut commands, &mut meshes, &mut materials, &config);
+}
+
+/// Spawns the two roads the app starts with, with a follower on the first
+fn spawn_starting_roads(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    config: &Config,
+) {
+    let point_count = match config.curve_type {
+        CurveType::Quadratic => 3,
+        CurveType::Bezier | CurveType::CatmullRom => 4,
+    };
+    let roads = [0.0, -3.0].map(|z| {
+        let control_points = (0..point_count)
+            .map(|i| Vec3::new(i as f32 * 9.0 / (point_count - 1) as f32, 0.0, z))
+            .collect::<Vec<_>>();
+        let road = commands
+            .spawn((
+                Road {
+                    control_points: control_points.clone(),
+                    detail: 20,
+                    ..default()
+                },
+                SpatialBundle::default(),
+            ))
+            .id();
+        spawn_control_points(commands, meshes, materials, road, &control_points, config);
+        road
+    });
+
+    // Something to drive along the first road
+    commands.entity(roads[0]).with_children(|parent| {
+        parent.spawn((
+            PathFollower {
+                distance: 0.0,
+                speed: 2.0,
+            },
+            PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::Box::new(0.15, 0.1, 0.25))),
+                material: materials.add(Color::YELLOW.into()),
+                ..default()
+            },
+        ));
+    });
+}
+
+/// Width of the demo terrain along each side
+const TERRAIN_SIZE: f32 = 16.0;
+/// Number of quads along each side of the demo terrain
+const TERRAIN_RESOLUTION: usize = 32;
+/// Tallest the demo terrain's hills rise above (and its dips fall below) its origin
+const TERRAIN_HILL_HEIGHT: f32 = 0.4;
+
+/// Square of gently rolling ground, `size` across and split into `resolution` quads a side
+fn terrain_mesh(size: f32, resolution: usize) -> Mesh {
+    let height = |x: f32, z: f32| TERRAIN_HILL_HEIGHT * (x * 0.6).sin() * (z * 0.8).cos();
+    let step = size / resolution as f32;
+    let mut mesh_data = MeshData::default();
+    for row in 0..=resolution {
+        for column in 0..=resolution {
+            let (x, z) = (
+                column as f32 * step - size / 2.0,
+                row as f32 * step - size / 2.0,
+            );
+            mesh_data.positions.push(Vec3::new(x, height(x, z), z));
+            // Slope across the neighbouring points either side
+            let dx = height(x + step, z) - height(x - step, z);
+            let dz = height(x, z + step) - height(x, z - step);
+            mesh_data
+                .normals
+                .push(Vec3::new(-dx, 2.0 * step, -dz).normalize());
+            mesh_data.uvs.push(Vec2::new(
+                column as f32 / resolution as f32,
+                row as f32 / resolution as f32,
+            ));
+        }
+    }
+    let row_length = resolution as u32 + 1;
+    for row in 0..resolution as u32 {
+        for column in 0..resolution as u32 {
+            let corner = row * row_length + column;
+            let (right, below) = (corner + 1, corner + row_length);
+            mesh_data
+                .indices
+                .extend([corner, below, right, right, below, below + 1]);
+        }
+    }
+    mesh_data.into_mesh()
+}
+
+fn spawn_control_points(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    road: Entity,
+    control_points: &[Vec3],
+    config: &Config,
+) {
+    for (i, point) in control_points.iter().enumerate() {
         commands.spawn((
-            ControlPoint(i),
+            ControlPoint { road, index: i },
             PbrBundle {
                 mesh: meshes.add(Mesh::from(shape::UVSphere {
-                    radius: 0.05,
+                    radius: config.point_radius,
                     ..default()
                 })),
-                material: materials.add(Color::RED.into()),
+                material: materials.add(
+                    if config.curve_type.is_anchor(i) {
+                        config.anchor_color
+                    } else {
+                        config.handle_color
+                    }
+                    .into(),
+                ),
                 transform: Transform::from_translation(*point),
                 ..default()
             },
@@ -123,210 +860,2704 @@ fn setup(
     }
 }
 
-fn build_mesh(
+/// Respawns the `ControlPoint` spheres when their radius or colours change
+fn restyle_control_points(
+    config: Res<Config>,
+    road_q: Query<Entity, With<Road>>,
+    mut respawn_events: EventWriter<RespawnControlPoints>,
+    mut applied_style: Local<Option<(f32, Color, Color)>>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    let style = (
+        config.point_radius,
+        config.anchor_color,
+        config.handle_color,
+    );
+    // The first style is the one the points were spawned with
+    if applied_style
+        .replace(style)
+        .map_or(false, |applied| applied != style)
+    {
+        respawn_events.send_batch(road_q.iter().map(RespawnControlPoints));
+    }
+}
+
+/// Camera distance at which scaled control points are drawn at `Config.point_radius`
+const POINT_SCALE_DISTANCE: f32 = 5.0;
+
+/// Scales each `ControlPoint` sphere to keep roughly the same size on screen
+fn scale_control_points(
+    config: Res<Config>,
+    camera_q: Query<&GlobalTransform, With<PanOrbitCamera>>,
+    mut point_q: Query<&mut Transform, With<ControlPoint>>,
+) {
+    let Ok(camera) = camera_q.get_single() else {
+        return;
+    };
+    for mut transform in point_q.iter_mut() {
+        let scale = if config.scale_points_with_distance {
+            (camera.translation().distance(transform.translation) / POINT_SCALE_DISTANCE).max(1.0)
+        } else {
+            1.0
+        };
+        // Only touch the transform when it changes, so it isn't flagged as changed every frame
+        if transform.scale != Vec3::splat(scale) {
+            transform.scale = Vec3::splat(scale);
+        }
+    }
+}
+
+/// Replaces a road's `ControlPoint` spheres with ones spawned from `Road.control_points`
+fn respawn_control_points(
     mut commands: Commands,
+    mut events: EventReader<RespawnControlPoints>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    mut debug_materials: ResMut<Assets<UvDebugMaterial>>,
+    config: Res<Config>,
+    road_q: Query<&Road>,
+    point_q: Query<(Entity, &ControlPoint)>,
+) {
+    for &RespawnControlPoints(road_entity) in events.iter().unique_by(|event| event.0) {
+        let Ok(road) = road_q.get(road_entity) else {
+            continue;
+        };
+        for (entity, _) in point_q.iter().filter(|(_, cp)| cp.road == road_entity) {
+            commands.entity(entity).despawn_recursive();
+        }
+        spawn_control_points(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            road_entity,
+            &road.control_points,
+            &config,
+        );
+    }
+}
+
+/// Panel for adding and removing segments, and joining the selected roads
+fn segment_panel(
+    mut contexts: EguiContexts,
+    config: Res<Config>,
+    mut road_q: Query<(Entity, &mut Road)>,
     point_q: Query<(&ControlPoint, &Transform)>,
-    mut config: ResMut<Config>,
-    asset_server: Res<AssetServer>,
-    mut painter: ShapePainter,
+    selection_q: Query<(&ControlPoint, &Selection)>,
+    mut respawn_events: EventWriter<RespawnControlPoints>,
+) {
+    // Bézier segments take three more points, quadratics two and Catmull-Rom spans only one
+    let points_per_segment = config.curve_type.points_per_segment();
+    let min_points = points_per_segment + 1;
+    egui::Window::new("Segments").show(contexts.ctx_mut(), |ui| {
+        for (i, (entity, mut road)) in road_q
+            .iter_mut()
+            .sorted_by_key(|(entity, _)| *entity)
+            .enumerate()
+        {
+            ui.label(format!("Road {}", i + 1));
+            ui.horizontal(|ui| {
+                if ui.button("Append segment").clicked() {
+                    let mut control_points = control_point_positions(&point_q, entity);
+                    if let Some(segment) = extrapolate_segment(&control_points) {
+                        control_points.extend(&segment[..points_per_segment]);
+                        road.control_points = control_points;
+                        respawn_events.send(RespawnControlPoints(entity));
+                    }
+                }
+                if ui.button("Remove last segment").clicked() {
+                    let mut control_points = control_point_positions(&point_q, entity);
+                    // Always keep at least one segment
+                    if control_points.len() > min_points {
+                        control_points.truncate(control_points.len() - points_per_segment);
+                        road.control_points = control_points;
+                        respawn_events.send(RespawnControlPoints(entity));
+                    }
+                }
+                let import = ui
+                    .add_enabled(
+                        config.curve_type == CurveType::Bezier,
+                        egui::Button::new("Import SVG"),
+                    )
+                    .on_hover_text("Replace the road with the first path in svg_file")
+                    .on_disabled_hover_text("SVG paths are only imported as cubic Béziers");
+                if import.clicked() {
+                    let imported = std::fs::read_to_string(&config.svg_file)
+                        .map_err(|err| err.to_string())
+                        .and_then(|svg| import_svg(&svg).map_err(|err| err.to_string()));
+                    match imported {
+                        Ok(points) if points.len() >= min_points => {
+                            road.control_points = points
+                                .into_iter()
+                                .map(|point| Vec3::new(point.x, 0.0, point.y) * config.svg_scale)
+                                .collect();
+                            respawn_events.send(RespawnControlPoints(entity));
+                        }
+                        Ok(_) => error!("{} has no segments to import", config.svg_file),
+                        Err(err) => error!("Failed to import {}: {err}", config.svg_file),
+                    }
+                }
+            });
+            let length = curve_length(&curve_points(&point_q, entity, &config));
+            ui.label(format!("Length: {length:.2}"));
+        }
+
+        let selected_roads = selection_q
+            .iter()
+            .filter(|(_, selection)| selection.selected())
+            .map(|(cp, _)| cp.road)
+            .unique()
+            .collect::<Vec<_>>();
+        // Catmull-Rom waypoints have no handles to line up
+        let can_join = selected_roads.len() == 2 && config.curve_type != CurveType::CatmullRom;
+        let join = ui
+            .add_enabled(can_join, egui::Button::new("Join selected roads"))
+            .on_hover_text("Select a control point on each of two roads");
+        if join.clicked() {
+            let [mut a, mut b] =
+                [0, 1].map(|i| control_point_positions(&point_q, selected_roads[i]));
+            // Join the end of whichever road finishes closest to the start of the other
+            let end_to_start = |from: &[Vec3], to: &[Vec3]| match (from.last(), to.first()) {
+                (Some(end), Some(start)) => end.distance(*start),
+                _ => f32::INFINITY,
+            };
+            if end_to_start(&a, &b) <= end_to_start(&b, &a) {
+                join_chains(&mut a, &mut b);
+            } else {
+                join_chains(&mut b, &mut a);
+            }
+            for (entity, control_points) in selected_roads.into_iter().zip([a, b]) {
+                if let Ok((_, mut road)) = road_q.get_mut(entity) {
+                    road.control_points = control_points;
+                    respawn_events.send(RespawnControlPoints(entity));
+                }
+            }
+        }
+    });
+}
+
+/// Panel showing the generated meshes' size and the frame rate
+fn stats_panel(
+    mut contexts: EguiContexts,
+    config: Res<Config>,
+    diagnostics: Res<Diagnostics>,
+    road_stats: Res<RoadStats>,
+    generation_state: Res<GenerationState>,
+    type_registry: Res<AppTypeRegistry>,
+    mut rebuild_events: EventWriter<RebuildMeshes>,
+) {
+    let smoothed = |id| {
+        diagnostics
+            .get(id)
+            .and_then(|diagnostic| diagnostic.smoothed())
+    };
+    egui::Window::new("Stats").show(contexts.ctx_mut(), |ui| {
+        // Drawn by the inspector like the config, but read-only since it's only ever measured
+        ui_for_value_readonly(&*road_stats, ui, &type_registry.read());
+        if let (Some(fps), Some(frame_time)) = (
+            smoothed(FrameTimeDiagnosticsPlugin::FPS),
+            smoothed(FrameTimeDiagnosticsPlugin::FRAME_TIME),
+        ) {
+            ui.label(format!("FPS: {fps:.0} ({frame_time:.2} ms)"));
+        }
+        // Edits are picked up straight away with auto update on
+        if ui
+            .add_enabled(!config.auto_update, egui::Button::new("Rebuild now"))
+            .clicked()
+        {
+            rebuild_events.send(RebuildMeshes);
+        }
+        if *generation_state == GenerationState::Dirty {
+            ui.label("Edits waiting to be built");
+        }
+        if config.uv_layout == UvLayout::Atlas {
+            // Each face's share of U, going round the profile, for painting a texture to match
+            let groups = profile_surfaces(&config.profile_kind, config.lanes)
+                .into_iter()
+                .flat_map(|(group, edges)| edges.into_iter().map(move |edge| (edge, group)))
+                .collect::<HashMap<_, _>>();
+            ui.collapsing("UV atlas", |ui| {
+                let edges = profile_edges(&config.profile_kind, config.lanes);
+                let ranges = atlas_ranges(&config.profile_kind, config.lanes);
+                for (face, (edge, range)) in edges.iter().zip(ranges).enumerate() {
+                    ui.label(format!(
+                        "Face {face} ({:?}): U {:.3} to {:.3}",
+                        groups[edge], range.start, range.end
+                    ));
+                }
+            });
+        }
+    });
+}
+
+/// How close to the centerline (in world units) a click has to land to insert a point
+const INSERT_PICK_DISTANCE: f32 = 0.5;
+
+/// In insert mode, clicking on a curve splits the segment under the cursor
+fn insert_control_point(
+    mouse: Res<Input<MouseButton>>,
+    mut contexts: EguiContexts,
+    config: Res<Config>,
+    camera_q: Query<&PickingCamera>,
+    mut road_q: Query<(Entity, &mut Road)>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    mut respawn_events: EventWriter<RespawnControlPoints>,
+) {
+    if !config.insert_mode
+        || !mouse.just_pressed(MouseButton::Left)
+        || contexts.ctx_mut().wants_pointer_input()
+    {
+        return;
+    }
+    let Some(ray) = camera_q.iter().find_map(|camera| camera.get_ray()) else {
+        return;
+    };
+    // Split whichever road passes closest to the cursor
+    let Some((entity, mut road, chain, (segment, t, distance))) = road_q
+        .iter_mut()
+        .filter_map(|(entity, road)| {
+            let chain = curve_points(&point_q, entity, &config);
+            let nearest = nearest_to_ray(&chain, ray.origin(), ray.direction())?;
+            Some((entity, road, chain, nearest))
+        })
+        .min_by(|(.., (.., x)), (.., (.., y))| x.total_cmp(y))
+    else {
+        return;
+    };
+    // Splitting right at an existing anchor would only stack points on top of each other
+    if distance < INSERT_PICK_DISTANCE && t > 0.0 && t < 1.0 {
+        let mut control_points = control_point_positions(&point_q, entity);
+        match config.curve_type {
+            // The closing segment of a loop isn't stored, so there's nothing to split
+            CurveType::Bezier if (segment + 1) * 3 >= control_points.len() => return,
+            CurveType::Bezier => insert_anchor(&mut control_points, segment, t),
+            CurveType::Quadratic if (segment + 1) * 2 >= control_points.len() => return,
+            CurveType::Quadratic => insert_quadratic_anchor(&mut control_points, segment, t),
+            CurveType::CatmullRom => {
+                let [a, b, c, d] = segments(&chain).nth(segment).unwrap();
+                control_points.insert(segment + 1, cubic_bezier(a, b, c, d, t));
+            }
+        }
+        road.control_points = control_points;
+        respawn_events.send(RespawnControlPoints(entity));
+    }
+}
+
+/// Keys that nudge the selected control points, and the direction each moves them in
+const NUDGE_KEYS: [(KeyCode, Vec3); 6] = [
+    (KeyCode::Right, Vec3::X),
+    (KeyCode::Left, Vec3::NEG_X),
+    (KeyCode::Up, Vec3::NEG_Z),
+    (KeyCode::Down, Vec3::Z),
+    (KeyCode::PageUp, Vec3::Y),
+    (KeyCode::PageDown, Vec3::NEG_Y),
+];
+
+/// Moves the selected control points a step at a time with the arrow keys
+fn nudge_control_points(
+    keys: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+    config: Res<Config>,
+    mut point_q: Query<(&Selection, &mut Transform), With<ControlPoint>>,
+    mut nudge_events: EventWriter<PointsNudged>,
 ) {
-    if let Some(((_, tfm1), (_, tfm2), (_, tfm3), (_, tfm4))) = point_q
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    let direction = NUDGE_KEYS
         .iter()
-        .sorted_by_key(|(cp, _)| cp.0)
-        .tuples::<(_, _, _, _)>()
-        .last()
+        .filter(|(key, _)| keys.just_pressed(*key))
+        .map(|(_, direction)| *direction)
+        .sum::<Vec3>();
+    if direction == Vec3::ZERO {
+        return;
+    }
+    let step = if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        config.nudge_step * 10.0
+    } else {
+        config.nudge_step
+    };
+    let mut nudged = false;
+    for (_, mut transform) in point_q
+        .iter_mut()
+        .filter(|(selection, _)| selection.selected())
     {
-        let vertices = (0..config.detail)
-            .map(|i| i as f32 / (config.detail as f32 - 1.0))
-            .map(|t| {
-                (
-                    t,
-                    cubic_bezier(
-                        tfm1.translation,
-                        tfm2.translation,
-                        tfm3.translation,
-                        tfm4.translation,
-                        t,
-                    ),
-                )
-            })
-            .flat_map(|(t, curve_point)| {
-                // Vertices of one slice of road, relative to the point on the curve
-                #[rustfmt::skip]
-                let local_vertices = vec![
-                    // 0
-                    Vertex::new(Vec3::new(-0.5, 0.3, 0.0), Vec3::NEG_X, Vec2::new(0.0, t)),
-                    Vertex::new(Vec3::new(-0.5, 0.3, 0.0), Vec3::Y, Vec2::new(0.0, t)),
-                    // 1
-                    Vertex::new(Vec3::new(-0.3, 0.3, 0.0), Vec3::Y, Vec2::new(0.05, t)),
-                    Vertex::new(Vec3::new(-0.3, 0.3, 0.0), Vec3::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), Vec2::new(0.05, t)),
-                    // 2
-                    Vertex::new(Vec3::new(-0.2, 0.2, 0.0), Vec3::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), Vec2::new(0.1, t)),
-                    Vertex::new(Vec3::new(-0.2, 0.2, 0.0), Vec3::Y, Vec2::new(0.1, t)),
-                    // 3
-                    Vertex::new(Vec3::new(0.2, 0.2, 0.0), Vec3::Y, Vec2::new(0.9, t)),
-                    Vertex::new(Vec3::new(0.2, 0.2, 0.0), Vec3::new(-FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), Vec2::new(0.9, t)),
-                    // 4
-                    Vertex::new(Vec3::new(0.3, 0.3, 0.0), Vec3::new(-FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), Vec2::new(0.95, t)),
-                    Vertex::new(Vec3::new(0.3, 0.3, 0.0), Vec3::Y, Vec2::new(0.95, t)),
-                    // 5
-                    Vertex::new(Vec3::new(0.5, 0.3, 0.0), Vec3::Y, Vec2::new(1.0, t)),
-                    Vertex::new(Vec3::new(0.5, 0.3, 0.0), Vec3::X, Vec2::new(1.0, t)),
-                    // 6
-                    Vertex::new(Vec3::new(0.5, 0.0, 0.0), Vec3::X, Vec2::new(1.0, t)),
-                    Vertex::new(Vec3::new(0.5, 0.0, 0.0), Vec3::NEG_Y, Vec2::new(1.0, t)),
-                    // 7
-                    Vertex::new(Vec3::new(-0.5, 0.0, 0.0), Vec3::NEG_Y, Vec2::new(1.0, t)),
-                    Vertex::new(Vec3::new(-0.5, 0.0, 0.0), Vec3::NEG_X, Vec2::new(1.0, t)),
-                ];
-
-                // Map these local points to world points by adding them to the curve point
-                local_vertices.into_iter().map(move |mut local_vertex| {
-                    let bez_mat = cubic_bezier_matrix(
-                            tfm1.translation,
-                            tfm2.translation,
-                            tfm3.translation,
-                            tfm4.translation,
-                            t,
-                    );
-                    local_vertex.point = bez_mat.transform_point3(local_vertex.point);
-                    local_vertex.normal = bez_mat.transform_vector3(local_vertex.normal);
-                    local_vertex
-                })
-            })
-            .collect::<Vec<_>>();
+        transform.translation += direction * step;
+        nudged = true;
+    }
+    if nudged {
+        nudge_events.send(PointsNudged);
+    }
+}
 
-        // debug
-        // for v in vertices.iter() {
-        //     // println!("spawning at: {v}");
-        //     commands.spawn((
-        //         PbrBundle {
-        //             mesh: meshes.add(Mesh::from(shape::UVSphere {
-        //                 radius: 0.02,
-        //                 ..default()
-        //             })),
-        //             material: materials.add(Color::RED.into()),
-        //             transform: Transform::from_translation(*v),
-        //             ..default()
-        //         },
-        //         Generated,
-        //     ));
-        // }
-
-        // Debug normals
-        // painter.thickness = 0.005;
-        // painter.cap = Cap::None;
-        // for v in vertices.iter() {
-        //     let color = Color::rgb(v.normal.x, v.normal.y, v.normal.z);
-        //     let dest = v.point + v.normal * 0.15;
-        //     painter.color = color;
-        //     painter.line(v.point, dest);
-        // }
-
-        let mut triangles: Vec<u32> = vec![];
-        for i in 0..(config.detail - 1) {
-            #[rustfmt::skip]
-                let base_tris: Vec<u32> = vec![
-                0, 16,31,
-                1, 18,17,
-                1, 2, 18,
-                3, 20,19,
-                3, 4, 20,
-                5, 22,21,
-                5, 6, 22,
-                7, 24,23,
-                7, 8, 24,
-                9, 26,25,
-                9, 10,26,
-                11,28,27,
-                11,12,28,
-                13,30,29,
-                13,14,30,
-                15,16,31,
-                15,0, 16,
-            ];
-            for j in base_tris {
-                triangles.push(j + (i * 16) as u32);
-            }
-        }
-
-        let vert_points = vertices.iter().map(|v| v.point).collect::<Vec<_>>();
-        let vert_normals = vertices.iter().map(|v| v.normal).collect::<Vec<_>>();
-        let vert_uvs = vertices.iter().map(|v| v.uv).collect::<Vec<_>>();
-
-        if let Some(mesh_handle) = &config.mesh {
-            let mesh = meshes.get_mut(mesh_handle).unwrap();
-            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vert_points);
-            mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vert_normals);
-            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vert_uvs);
-            mesh.set_indices(Some(Indices::U32(triangles)));
-        } else {
-            let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
-            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vert_points);
-            mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vert_normals);
-            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vert_uvs);
-            mesh.set_indices(Some(Indices::U32(triangles)));
-            let handle = meshes.add(mesh);
-
-            let road_tex_handle = asset_server.load("road.png");
-
-            commands.spawn((
-                Generated,
-                PbrBundle {
-                    mesh: handle.clone(),
-                    material: materials.add(StandardMaterial {
-                        base_color_texture: Some(road_tex_handle),
-                        ..default()
-                    }),
-                    ..default()
+/// Rounds dragged control points to the snapping grid once the gizmo is released
+fn snap_control_points(
+    config: Res<Config>,
+    mut gizmo_events: EventReader<TransformGizmoEvent>,
+    mut point_q: Query<(&Selection, &mut Transform), With<ControlPoint>>,
+) {
+    if gizmo_events.is_empty() {
+        return;
+    }
+    gizmo_events.clear();
+    let Some(snap) = config.grid_snap.filter(|snap| *snap > 0.0) else {
+        return;
+    };
+    for (_, mut transform) in point_q
+        .iter_mut()
+        .filter(|(selection, _)| selection.selected())
+    {
+        transform.translation = (transform.translation / snap).round() * snap;
+    }
+}
+
+/// Copies moved sphere positions back into their `Road` as an edit
+fn store_dragged_points(
+    mut gizmo_events: EventReader<TransformGizmoEvent>,
+    mut nudge_events: EventReader<PointsNudged>,
+    mut road_q: Query<(Entity, &mut Road)>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+) {
+    if gizmo_events.is_empty() && nudge_events.is_empty() {
+        return;
+    }
+    gizmo_events.clear();
+    nudge_events.clear();
+    for (entity, mut road) in road_q.iter_mut() {
+        let control_points = control_point_positions(&point_q, entity);
+        if road.control_points != control_points {
+            road.control_points = control_points;
+        }
+    }
+}
+
+/// Keeps a `MirrorOf` copy of every road while `Config.mirror_plane` is set
+#[allow(clippy::too_many_arguments)]
+fn sync_mirrors(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut respawn_events: EventWriter<RespawnControlPoints>,
+    config: Res<Config>,
+    mut road_q: Query<(Entity, &mut Road, Option<&MirrorOf>)>,
+    mut point_q: Query<(&ControlPoint, &mut Transform)>,
+) {
+    let Some(axis) = config.mirror_plane else {
+        for (entity, _, mirror) in road_q.iter() {
+            if mirror.is_some() {
+                commands.entity(entity).remove::<MirrorOf>();
+            }
+        }
+        return;
+    };
+
+    let mirrored = road_q
+        .iter()
+        .filter_map(|(_, _, mirror)| mirror.map(|&MirrorOf(original)| original))
+        .collect::<HashSet<_>>();
+    let mut originals = HashMap::new();
+    for (entity, road, mirror) in road_q.iter() {
+        if mirror.is_some() {
+            continue;
+        }
+        originals.insert(entity, road.control_points.clone());
+        if mirrored.contains(&entity) {
+            continue;
+        }
+        let control_points = road
+            .control_points
+            .iter()
+            .map(|&point| axis.reflect(point))
+            .collect::<Vec<_>>();
+        let copy = commands
+            .spawn((
+                Road {
+                    control_points: control_points.clone(),
+                    detail: road.detail,
+                    segment_textures: road.segment_textures.clone(),
                 },
-                // MaterialMeshBundle {
-                //     mesh: handle.clone(),
-                //     material: debug_materials.add(UvDebugMaterial::default()),
-                //     ..default()
-                // },
-            ));
+                SpatialBundle::default(),
+                MirrorOf(entity),
+            ))
+            .id();
+        spawn_control_points(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            copy,
+            &control_points,
+            &config,
+        );
+    }
+
+    let mut copies = HashMap::new();
+    for (entity, mut road, mirror) in road_q.iter_mut() {
+        let Some(&MirrorOf(original)) = mirror else {
+            continue;
+        };
+        let Some(original_points) = originals.get(&original) else {
+            commands.entity(entity).remove::<MirrorOf>();
+            continue;
+        };
+        copies.insert(entity, original);
+        let control_points = original_points
+            .iter()
+            .map(|&point| axis.reflect(point))
+            .collect::<Vec<_>>();
+        if road.control_points != control_points {
+            if road.control_points.len() != control_points.len() {
+                respawn_events.send(RespawnControlPoints(entity));
+            }
+            road.control_points = control_points;
+        }
+    }
 
-            config.mesh = Some(handle);
+    // The originals' spheres run ahead of their `Road` during a drag, so the copies follow them
+    let sphere_positions = point_q
+        .iter()
+        .filter(|(cp, _)| originals.contains_key(&cp.road))
+        .map(|(cp, transform)| ((cp.road, cp.index), transform.translation))
+        .collect::<HashMap<_, _>>();
+    for (cp, mut transform) in point_q.iter_mut() {
+        let Some(&position) = copies
+            .get(&cp.road)
+            .and_then(|&original| sphere_positions.get(&(original, cp.index)))
+        else {
+            continue;
+        };
+        let reflected = axis.reflect(position);
+        if transform.translation != reflected {
+            transform.translation = reflected;
         }
     }
 }
 
-fn cubic_bezier(a: Vec3, b: Vec3, c: Vec3, d: Vec3, t: f32) -> Vec3 {
-    let ab = a.lerp(b, t);
-    let bc = b.lerp(c, t);
-    let cd = c.lerp(d, t);
-    let abbc = ab.lerp(bc, t);
-    let bccd = bc.lerp(cd, t);
-    abbc.lerp(bccd, t)
+/// Pushes the previous layouts onto the undo stack whenever a road's control points change
+fn record_history(mut history: ResMut<History>, road_q: Query<(Entity, &Road), Changed<Road>>) {
+    let mut previous = history.current.clone();
+    let mut edited = false;
+    for (entity, road) in road_q.iter() {
+        match history.current.insert(entity, road.control_points.clone()) {
+            Some(control_points) if control_points != road.control_points => edited = true,
+            // A road's first layout isn't an edit
+            None => {
+                previous.insert(entity, road.control_points.clone());
+            }
+            _ => {}
+        }
+    }
+    if edited {
+        if history.undo.len() == MAX_UNDO_DEPTH {
+            history.undo.pop_front();
+        }
+        history.undo.push_back(previous);
+        history.redo.clear();
+    }
 }
 
-fn cubic_bezier_matrix(a: Vec3, b: Vec3, c: Vec3, d: Vec3, t: f32) -> Mat4 {
-    let ab = a.lerp(b, t);
-    let bc = b.lerp(c, t);
-    let cd = c.lerp(d, t);
-    let abbc = ab.lerp(bc, t);
-    let bccd = bc.lerp(cd, t);
-    let position = abbc.lerp(bccd, t);
-    let z = (abbc - bccd).normalize();
-    let y = Vec3::Y;
-    let x = y.cross(z);
-    Mat4::from_cols(
-        Vec4::from((x, 0.0)),
-        Vec4::from((y, 0.0)),
-        Vec4::from((z, 0.0)),
-        Vec4::from((position, 1.0)),
-    )
+/// Ctrl+N starts over with the starting roads and no undo history
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn reset_roads(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    config: Res<Config>,
+    mut history: ResMut<History>,
+    road_q: Query<Entity, Or<(With<Road>, With<ControlPoint>)>>,
+) {
+    if !keys.any_pressed([KeyCode::LControl, KeyCode::RControl])
+        || !keys.just_pressed(KeyCode::N)
+        || contexts.ctx_mut().wants_keyboard_input()
+    {
+        return;
+    }
+    // Surfaces, guardrails and followers are children of their road, so they go with it
+    for entity in road_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_starting_roads(&mut commands, &mut meshes, &mut materials, &config);
+    // The new roads' layouts are recorded as a starting point rather than as an edit
+    *history = History::default();
 }
 
-fn draw_polyline(points: Vec<(Vec3, Color)>, painter: &mut ShapePainter) {
-    for window in points.windows(2) {
-        let (point_1, color_1) = window[0];
-        let (point_2, _) = window[1];
-        painter.color = color_1;
-        painter.line(point_1, point_2);
+/// Ctrl+Z restores the layouts from before the last edit, Ctrl+Shift+Z reapplies an undone edit
+fn undo_redo(
+    keys: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+    mut history: ResMut<History>,
+    mut road_q: Query<&mut Road>,
+    mut respawn_events: EventWriter<RespawnControlPoints>,
+) {
+    if !keys.any_pressed([KeyCode::LControl, KeyCode::RControl])
+        || !keys.just_pressed(KeyCode::Z)
+        || contexts.ctx_mut().wants_keyboard_input()
+    {
+        return;
+    }
+    let History {
+        undo,
+        redo,
+        current,
+    } = &mut *history;
+    let layouts = if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        redo.pop().map(|layouts| {
+            undo.push_back(current.clone());
+            layouts
+        })
+    } else {
+        undo.pop_back().map(|layouts| {
+            redo.push(current.clone());
+            layouts
+        })
+    };
+    let Some(layouts) = layouts else {
+        return;
+    };
+    for (&entity, control_points) in &layouts {
+        if let Ok(mut road) = road_q.get_mut(entity) {
+            road.control_points = control_points.clone();
+            respawn_events.send(RespawnControlPoints(entity));
+        }
+    }
+    // Restoring a layout isn't an edit of its own
+    *current = layouts;
+}
+
+/// Furthest control points wander from where they started in demo mode
+const DRIFT_AMPLITUDE: f32 = 0.3;
+/// How quickly control points drift in demo mode, in radians of phase per second
+const DRIFT_SPEED: f32 = 0.8;
+
+/// Offset of a drifting point from where it started, `seconds` into its loop
+fn drift_offset(road: Entity, anchor: usize, seconds: f32) -> Vec3 {
+    // Golden angle, so no two seeds line up for long
+    let seed = (road.index() as usize * 31 + anchor) as f32 * 2.399_963;
+    let angle = seconds * DRIFT_SPEED + seed;
+    DRIFT_AMPLITUDE
+        * Vec3::new(
+            angle.sin(),
+            0.5 * (1.3 * angle + seed).sin(),
+            (0.7 * angle + 2.0 * seed).cos(),
+        )
+}
+
+/// In demo mode, moves every anchor and its handles along a slow loop
+fn drift_control_points(
+    time: Res<Time>,
+    config: Res<Config>,
+    mut road_q: Query<(Entity, &mut Road)>,
+    mut point_q: Query<(&ControlPoint, &mut Transform)>,
+    mut drifting: Local<bool>,
+) {
+    if !config.demo_mode {
+        if std::mem::take(&mut *drifting) {
+            for (entity, mut road) in road_q.iter_mut() {
+                road.control_points = point_q
+                    .iter()
+                    .filter(|(cp, _)| cp.road == entity)
+                    .sorted_by_key(|(cp, _)| cp.index)
+                    .map(|(_, tfm)| tfm.translation)
+                    .collect();
+            }
+        }
+        return;
+    }
+    *drifting = true;
+    let now = time.elapsed_seconds();
+    let before = now - time.delta_seconds();
+    for (cp, mut transform) in point_q.iter_mut() {
+        // Quadratic handles belong to two anchors at once, so they drift on their own
+        let anchor = match config.curve_type {
+            CurveType::Bezier => (cp.index + 1) / 3 * 3,
+            CurveType::CatmullRom | CurveType::Quadratic => cp.index,
+        };
+        transform.translation +=
+            drift_offset(cp.road, anchor, now) - drift_offset(cp.road, anchor, before);
+    }
+}
+
+/// Keeps handles in line through their anchor with `Config.enforce_g1`, unless Alt is held
+fn constrain_handles(
+    keys: Res<Input<KeyCode>>,
+    config: Res<Config>,
+    mut point_q: Query<(&ControlPoint, &mut Transform)>,
+    added_q: Query<&ControlPoint, Added<ControlPoint>>,
+    mut previous_points: Local<HashMap<Entity, Vec<Vec3>>>,
+) {
+    // Catmull-Rom waypoints have no handles, and each quadratic handle is shared by the segments
+    // either side, so there's no opposite handle to mirror. Drifting moves handles along with their
+    // anchors already. Forgetting the previous positions stops everything moved in the meantime
+    // from being read as a drag afterwards
+    if config.curve_type != CurveType::Bezier || config.demo_mode {
+        previous_points.clear();
+        return;
+    }
+    let added_roads = added_q.iter().map(|cp| cp.road).collect::<HashSet<_>>();
+    let roads = point_q
+        .iter_mut()
+        .sorted_by_key(|(cp, _)| cp.index)
+        .into_group_map_by(|(cp, _)| cp.road);
+    for (road, points) in roads {
+        let mut points = points.into_iter().map(|(_, tfm)| tfm).collect::<Vec<_>>();
+        let previous_points = previous_points.entry(road).or_default();
+        // Freshly spawned points haven't been dragged, so there's nothing to constrain
+        if previous_points.len() == points.len() && !added_roads.contains(&road) {
+            let moved = (0..points.len()).find(|&i| {
+                points[i].translation.distance_squared(previous_points[i]) > f32::EPSILON
+            });
+            if let Some(i) = moved {
+                if config.curve_type.is_anchor(i) {
+                    let delta = points[i].translation - previous_points[i];
+                    for handle in [i.wrapping_sub(1), i + 1] {
+                        if let Some(tfm) = points.get_mut(handle) {
+                            tfm.translation += delta;
+                        }
+                    }
+                } else if config.enforce_g1 && !keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]) {
+                    let (anchor, opposite) = if config.curve_type.is_anchor(i - 1) {
+                        (i - 1, i.checked_sub(2))
+                    } else {
+                        (i + 1, Some(i + 2))
+                    };
+                    if let Some(opposite) = opposite.filter(|&opposite| opposite < points.len()) {
+                        let anchor = points[anchor].translation;
+                        let mirrored = anchor - points[i].translation;
+                        let length = if config.preserve_handle_lengths {
+                            points[opposite].translation.distance(anchor)
+                        } else {
+                            mirrored.length()
+                        };
+                        // A handle dragged onto its anchor has no direction to line up with
+                        if let Some(direction) = mirrored.try_normalize() {
+                            points[opposite].translation = anchor + direction * length;
+                        }
+                    }
+                }
+            }
+        }
+        *previous_points = points.iter().map(|tfm| tfm.translation).collect();
+    }
+}
+
+/// Draws each vertex normal of the generated mesh as a line coloured by its direction
+fn draw_normals(
+    config: Res<Config>,
+    meshes: Res<Assets<Mesh>>,
+    surface_q: Query<&Handle<Mesh>, With<RoadSurface>>,
+    mut painter: ShapePainter,
+) {
+    if !config.debug_normals {
+        return;
+    }
+    painter.thickness = config.normal_line_thickness;
+    painter.cap = Cap::None;
+    for mesh in surface_q.iter().filter_map(|handle| meshes.get(handle)) {
+        let (
+            Some(VertexAttributeValues::Float32x3(positions)),
+            Some(VertexAttributeValues::Float32x3(normals)),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+        )
+        else {
+            continue;
+        };
+        for (position, normal) in positions.iter().zip(normals) {
+            let (position, normal) = (Vec3::from(*position), Vec3::from(*normal));
+            painter.color = Color::rgb(normal.x, normal.y, normal.z);
+            painter.line(position, position + normal * config.normal_line_length);
+        }
+    }
+}
+
+/// How far the wireframe overlay is lifted off the surface along its normals
+const WIREFRAME_LIFT: f32 = 0.002;
+
+/// Draws each edge of the generated triangles once as a thin line
+fn draw_wireframe_overlay(
+    config: Res<Config>,
+    meshes: Res<Assets<Mesh>>,
+    surface_q: Query<&Handle<Mesh>, With<RoadSurface>>,
+    mut painter: ShapePainter,
+) {
+    if !config.show_wireframe_overlay {
+        return;
+    }
+    painter.thickness_type = ThicknessType::Pixels;
+    painter.thickness = 1.0;
+    painter.cap = Cap::None;
+    painter.color = Color::BLACK;
+    for mesh in surface_q.iter().filter_map(|handle| meshes.get(handle)) {
+        let (
+            Some(VertexAttributeValues::Float32x3(positions)),
+            Some(VertexAttributeValues::Float32x3(normals)),
+            Some(indices),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+            triangle_list(mesh),
+        )
+        else {
+            continue;
+        };
+        let lifted = |i: u32| {
+            let i = i as usize;
+            Vec3::from(positions[i]) + Vec3::from(normals[i]) * WIREFRAME_LIFT
+        };
+        let edges = indices
+            .chunks_exact(3)
+            .flat_map(|triangle| {
+                [[0, 1], [1, 2], [2, 0]].map(|[a, b]| {
+                    let (a, b) = (triangle[a], triangle[b]);
+                    (a.min(b), a.max(b))
+                })
+            })
+            // Degenerate triangles joining strips have edges that go nowhere
+            .filter(|(a, b)| a != b)
+            .collect::<HashSet<_>>();
+        for (a, b) in edges {
+            painter.line(lifted(a), lifted(b));
+        }
+    }
+}
+
+/// Draws a line from each anchor out to its handles
+fn draw_handles(
+    config: Res<Config>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    mut painter: ShapePainter,
+) {
+    if config.curve_type == CurveType::CatmullRom {
+        return;
+    }
+    painter.thickness = 0.01;
+    let roads = point_q
+        .iter()
+        .sorted_by_key(|(cp, _)| cp.index)
+        .into_group_map_by(|(cp, _)| cp.road);
+    for points in roads.values() {
+        for anchor in (0..points.len()).step_by(config.curve_type.points_per_segment()) {
+            let handle_line = [anchor.wrapping_sub(1), anchor, anchor + 1]
+                .into_iter()
+                .filter_map(|i| points.get(i))
+                .map(|(_, tfm)| (tfm.translation, config.handle_color))
+                .collect();
+            draw_polyline(handle_line, &mut painter);
+        }
+    }
+}
+
+/// Samples per segment of the overlaid centerline
+const OVERLAY_SAMPLES: usize = 32;
+/// How far the overlaid centerline floats above the top of the profile, so it doesn't z-fight
+const OVERLAY_NUDGE: f32 = 0.01;
+
+/// Draws each road's centerline and the polygon joining its control points
+fn draw_overlay(
+    config: Res<Config>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    road_q: Query<Entity, With<Road>>,
+    mut painter: ShapePainter,
+) {
+    if !config.show_overlay {
+        return;
+    }
+    painter.thickness = 0.01;
+    let lift =
+        Vec3::Y * (profile_top(&config.profile_kind) * config.profile_scale.y + OVERLAY_NUDGE);
+    for road in road_q.iter() {
+        let samples = segments(&curve_points(&point_q, road, &config))
+            .enumerate()
+            .flat_map(|(i, segment)| {
+                // Segments share end points, so every segment after the first skips its first sample
+                let first_sample = if i == 0 { 0 } else { 1 };
+                (first_sample..=OVERLAY_SAMPLES)
+                    .map(move |j| (segment, j as f32 / OVERLAY_SAMPLES as f32))
+            })
+            .collect::<Vec<_>>();
+        let centerline = samples
+            .iter()
+            .zip(curve_frames(&samples, config.frame_mode, config.up))
+            .map(|(&([a, b, c, d], t), (position, rotation))| {
+                let bank = bank_angle(cubic_bezier_curvature(a, b, c, d, t), config.auto_bank);
+                let frame = Mat4::from_rotation_translation(rotation, position)
+                    * Mat4::from_rotation_z(bank);
+                (frame.transform_point3(lift), Color::rgb(t, 1.0 - t, t))
+            })
+            .collect();
+        draw_polyline(centerline, &mut painter);
+        let polygon = control_point_positions(&point_q, road)
+            .into_iter()
+            .map(|point| (point, Color::WHITE))
+            .collect();
+        draw_polyline(polygon, &mut painter);
+    }
+}
+
+const GRID_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.2);
+
+/// Draws `Config.grid`, with the lines through the origin in the axis colours
+fn draw_grid(config: Res<Config>, mut painter: ShapePainter) {
+    let Some(grid) = config.grid.as_ref().filter(|grid| grid.cell_size > 0.0) else {
+        return;
+    };
+    painter.thickness = 0.005;
+    painter.cap = Cap::None;
+    let lines = (grid.extent / grid.cell_size).floor() as i32;
+    for i in -lines..=lines {
+        let offset = i as f32 * grid.cell_size;
+        for (direction, across, axis_color) in [
+            (Vec3::X, Vec3::Z, Color::RED),
+            (Vec3::Z, Vec3::X, Color::BLUE),
+        ] {
+            painter.color = if i == 0 { axis_color } else { GRID_COLOR };
+            let middle = across * offset;
+            painter.line(
+                middle - direction * grid.extent,
+                middle + direction * grid.extent,
+            );
+        }
+    }
+}
+
+/// Length of each axis drawn with `show_frames`
+const FRAME_AXIS_LENGTH: f32 = 0.2;
+
+/// Draws the axes of every `frame_stride`th slice's frame
+fn draw_frames(
+    config: Res<Config>,
+    meshes: Res<Assets<Mesh>>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    road_q: Query<(Entity, &Road)>,
+    terrain_q: Query<(&Handle<Mesh>, &GlobalTransform), With<Terrain>>,
+    mut painter: ShapePainter,
+) {
+    if !config.show_frames {
+        return;
+    }
+    painter.thickness = 0.01;
+    painter.cap = Cap::None;
+    for (entity, road) in road_q.iter() {
+        let slices = road_slices(
+            &curve_points(&point_q, entity, &config),
+            road.detail,
+            &config,
+        );
+        let frames = slice_frames(&slices, &config, &meshes, &terrain_q);
+        for frame in frames.into_iter().step_by(config.frame_stride.max(1)) {
+            let origin = frame.w_axis.truncate();
+            for (axis, color) in [
+                (frame.x_axis, Color::RED),
+                (frame.y_axis, Color::GREEN),
+                (-frame.z_axis, Color::BLUE),
+            ] {
+                painter.color = color;
+                painter.line(origin, origin + axis.truncate() * FRAME_AXIS_LENGTH);
+            }
+        }
+    }
+}
+
+/// Labels the distances between control points and, optionally, the arc length
+fn draw_measurements(
+    mut contexts: EguiContexts,
+    config: Res<Config>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    road_q: Query<Entity, With<Road>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+) {
+    if !config.show_measurements {
+        return;
+    }
+    let Ok((camera, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+    let Some(viewport_size) = camera.logical_viewport_size() else {
+        return;
+    };
+    let painter = contexts.ctx_mut().layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("measurements"),
+    ));
+    let label = |position: Vec3, align: egui::Align2, text: String, color: egui::Color32| {
+        // Viewport coordinates start from the bottom left, and egui's from the top left
+        let Some(point) = camera.world_to_viewport(camera_transform, position) else {
+            return;
+        };
+        painter.text(
+            egui::pos2(point.x, viewport_size.y - point.y),
+            align,
+            text,
+            egui::FontId::proportional(14.0),
+            color,
+        );
+    };
+    for road in road_q.iter() {
+        let positions = control_point_positions(&point_q, road);
+        for pair in positions.windows(2) {
+            let distance = pair[0].distance(pair[1]);
+            label(
+                (pair[0] + pair[1]) / 2.0,
+                egui::Align2::CENTER_BOTTOM,
+                format!("{distance:.2}"),
+                egui::Color32::WHITE,
+            );
+        }
+        if config.show_arc_lengths {
+            // Each segment of the chain ends on the next anchor, in the same order as the points
+            let curve = curve_points(&point_q, road, &config);
+            let arc_lengths =
+                std::iter::once(0.0).chain(segments(&curve).scan(0.0, |length, segment| {
+                    *length += curve_length(&segment);
+                    Some(*length)
+                }));
+            let anchors = positions
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| config.curve_type.is_anchor(*i));
+            for ((_, &anchor), arc_length) in anchors.zip(arc_lengths) {
+                label(
+                    // Below the point, clear of the distance labels
+                    anchor,
+                    egui::Align2::CENTER_TOP,
+                    format!("s = {arc_length:.2}"),
+                    egui::Color32::YELLOW,
+                );
+            }
+        }
+    }
+}
+
+/// Warns when a road starts to overlap itself, and draws the clashes with `show_overlaps`
+#[allow(clippy::type_complexity)]
+fn check_overlaps(
+    config: Res<Config>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    moved_q: Query<(Entity, &ControlPoint, &Transform), Changed<Transform>>,
+    road_q: Query<Entity, With<Road>>,
+    mut painter: ShapePainter,
+    mut overlaps: Local<HashMap<Entity, Vec<(Vec3, Vec3)>>>,
+    mut last_translations: Local<HashMap<Entity, Vec3>>,
+) {
+    last_translations.retain(|point, _| point_q.contains(*point));
+    let moved_points = translated_points(
+        moved_q
+            .iter()
+            .map(|(point, _, transform)| (point, transform.translation)),
+        &mut last_translations,
+    );
+    let Some(min_distance) = config.overlap_distance.filter(|distance| *distance > 0.0) else {
+        overlaps.clear();
+        return;
+    };
+    overlaps.retain(|road, _| road_q.contains(*road));
+    let moved_roads = moved_q
+        .iter_many(&moved_points)
+        .map(|(_, cp, _)| cp.road)
+        .collect::<HashSet<_>>();
+    for road in road_q.iter() {
+        if !config.is_changed() && !moved_roads.contains(&road) && overlaps.contains_key(&road) {
+            continue;
+        }
+        let table = ArcLengthTable::new(&curve_points(&point_q, road, &config));
+        let clashes = table
+            .close_approaches(min_distance, config.closed)
+            .into_iter()
+            .filter_map(|(a, b)| Some((table.point_at_distance(a)?, table.point_at_distance(b)?)))
+            .collect::<Vec<_>>();
+        let overlapping = !clashes.is_empty();
+        let was_overlapping = overlaps
+            .insert(road, clashes)
+            .map_or(false, |previous| !previous.is_empty());
+        // Only warn when a road starts overlapping, rather than on every edit while it does
+        if overlapping && !was_overlapping {
+            warn!("Road comes within {min_distance} of itself, so it will pass through itself");
+        }
+    }
+
+    if !config.show_overlaps {
+        return;
+    }
+    painter.thickness = 2.0;
+    painter.thickness_type = ThicknessType::Pixels;
+    painter.color = Color::FUCHSIA;
+    let lift = Vec3::Y * profile_top(&config.profile_kind) * config.profile_scale.y;
+    for &(a, b) in overlaps.values().flatten() {
+        painter.line(a + lift, b + lift);
+    }
+}
+
+/// Gap in pixels between a control point and its index label
+const POINT_LABEL_OFFSET: f32 = 8.0;
+
+fn draw_point_labels(
+    mut contexts: EguiContexts,
+    config: Res<Config>,
+    point_q: Query<(&ControlPoint, &GlobalTransform)>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+) {
+    if !config.show_point_labels {
+        return;
+    }
+    let Ok((camera, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+    let Some(viewport_size) = camera.logical_viewport_size() else {
+        return;
+    };
+    let painter = contexts.ctx_mut().layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("point_labels"),
+    ));
+    for (cp, transform) in point_q.iter() {
+        // Viewport coordinates start from the bottom left, and egui's from the top left
+        let Some(point) = camera.world_to_viewport(camera_transform, transform.translation())
+        else {
+            continue;
+        };
+        painter.text(
+            egui::pos2(
+                point.x + POINT_LABEL_OFFSET,
+                viewport_size.y - point.y - POINT_LABEL_OFFSET,
+            ),
+            egui::Align2::LEFT_BOTTOM,
+            cp.index.to_string(),
+            egui::FontId::monospace(12.0),
+            egui::Color32::WHITE,
+        );
+    }
+}
+
+/// Current positions of a road's `ControlPoint` spheres in index order
+fn control_point_positions(
+    point_q: &Query<(&ControlPoint, &Transform)>,
+    road: Entity,
+) -> Vec<Vec3> {
+    point_q
+        .iter()
+        .filter(|(cp, _)| cp.road == road)
+        .sorted_by_key(|(cp, _)| cp.index)
+        .map(|(_, tfm)| tfm.translation)
+        .collect()
+}
+
+/// Cubic Bézier chain described by a road's control points, closed for loops
+fn curve_points(
+    point_q: &Query<(&ControlPoint, &Transform)>,
+    road: Entity,
+    config: &Config,
+) -> Vec<Vec3> {
+    let control_points = control_point_positions(point_q, road);
+    match config.curve_type {
+        CurveType::CatmullRom => {
+            let alpha = if config.centripetal { 0.5 } else { 0.0 };
+            cardinal_chain(&control_points, config.tension, alpha, config.closed)
+        }
+        CurveType::Bezier if config.closed => closed_chain(&control_points),
+        CurveType::Bezier => control_points,
+        CurveType::Quadratic if config.closed => {
+            quadratic_chain(&closed_quadratic_chain(&control_points))
+        }
+        CurveType::Quadratic => quadratic_chain(&control_points),
+    }
+}
+
+/// Marks the roads `Dirty` when anything `build_mesh` reads has changed
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn mark_generation_dirty(
+    mut state: ResMut<GenerationState>,
+    config: Res<Config>,
+    moved_q: Query<(Entity, &Transform), (With<ControlPoint>, Changed<Transform>)>,
+    road_q: Query<(), Changed<Road>>,
+    gizmo_q: Query<&TransformGizmo>,
+    mut removed_points: RemovedComponents<ControlPoint>,
+    mut was_dragging: Local<bool>,
+    mut last_translations: Local<HashMap<Entity, Vec3>>,
+) {
+    let dragging = gizmo_q
+        .iter()
+        .any(|gizmo| gizmo.current_interaction().is_some());
+    let drag_ended = std::mem::replace(&mut *was_dragging, dragging) && !dragging;
+    let mut points_removed = false;
+    for point in removed_points.iter() {
+        last_translations.remove(&point);
+        points_removed = true;
+    }
+    // Every point is checked so the cache stays current, even when the roads are already dirty
+    let points_moved = !translated_points(
+        moved_q
+            .iter()
+            .map(|(point, transform)| (point, transform.translation)),
+        &mut last_translations,
+    )
+    .is_empty();
+    if *state == GenerationState::Idle
+        && (config.is_changed()
+            || points_moved
+            || !road_q.is_empty()
+            || points_removed
+            || drag_ended)
+    {
+        *state = GenerationState::Dirty;
+    }
+}
+
+/// Points among `moved` whose translation changed since `last_translations`
+fn translated_points(
+    moved: impl IntoIterator<Item = (Entity, Vec3)>,
+    last_translations: &mut HashMap<Entity, Vec3>,
+) -> Vec<Entity> {
+    moved
+        .into_iter()
+        .filter(|&(point, translation)| {
+            last_translations.insert(point, translation) != Some(translation)
+        })
+        .map(|(point, _)| point)
+        .collect()
+}
+
+/// Moves `Dirty` roads on to `Building` with auto update on, or on `RebuildMeshes`
+fn start_generation(
+    mut state: ResMut<GenerationState>,
+    config: Res<Config>,
+    mut rebuild_events: EventReader<RebuildMeshes>,
+) {
+    let requested = rebuild_events.iter().count() > 0;
+    let automatic = *state == GenerationState::Dirty && (config.auto_update || config.demo_mode);
+    if requested || automatic {
+        *state = GenerationState::Building;
+    }
+}
+
+/// Settles back to `Idle` once `build_mesh` has run
+fn finish_generation(mut state: ResMut<GenerationState>) {
+    if *state == GenerationState::Building {
+        *state = GenerationState::Idle;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build_mesh(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    road_q: Query<(Entity, &Road)>,
+    surface_q: Query<(Entity, &RoadSurface, &Handle<Mesh>, &Parent)>,
+    terrain_q: Query<(&Handle<Mesh>, &GlobalTransform), With<Terrain>>,
+    gizmo_q: Query<&TransformGizmo>,
+    config: Res<Config>,
+    mut last_weld_counts: Local<HashMap<Entity, (usize, usize)>>,
+    mut inverted_roads: Local<HashMap<Entity, bool>>,
+    mut mesh_buffers: Local<MeshData>,
+    mut segment_caches: Local<HashMap<Entity, SegmentCache>>,
+    mut clamped_roads: Local<HashMap<Entity, Option<usize>>>,
+    mut road_stats: ResMut<RoadStats>,
+    mut stats_by_road: Local<HashMap<Entity, RoadStats>>,
+    mut malformed_roads: Local<HashMap<Entity, usize>>,
+) {
+    segment_caches.retain(|entity, _| road_q.contains(*entity));
+    malformed_roads.retain(|entity, _| road_q.contains(*entity));
+    stats_by_road.retain(|entity, _| road_q.contains(*entity));
+    let preview = config.preview_detail.filter(|_| {
+        gizmo_q
+            .iter()
+            .any(|gizmo| gizmo.current_interaction().is_some())
+    });
+    for (entity, road) in road_q.iter() {
+        let control_points = curve_points(&point_q, entity, &config);
+        // A stray point that doesn't complete a segment would otherwise be dropped without a word.
+        // The road keeps its last mesh until the points make whole segments again
+        if !is_cubic_chain(&control_points) {
+            // Counted as placed, rather than after conversion to a cubic chain
+            let count = control_point_positions(&point_q, entity).len();
+            if malformed_roads.insert(entity, count) != Some(count) {
+                let per_segment = config.curve_type.points_per_segment();
+                let (shape, rule) = if config.closed {
+                    ("closed", format!("a multiple of {per_segment}"))
+                } else {
+                    ("open", format!("a multiple of {per_segment} plus one"))
+                };
+                let curve_type = config.curve_type;
+                warn!("Road has {count} control points, but {curve_type:?} curves need {rule} when {shape}, so its mesh isn't being updated");
+            }
+            continue;
+        }
+        malformed_roads.remove(&entity);
+        let cache = segment_caches.entry(entity).or_default();
+        // Any setting can change how every slice is placed or sliced
+        if config.is_changed() {
+            cache.segments.clear();
+        }
+        let dirty = cache.update(&control_points, road.detail, preview, &config);
+        let built = surface_q.iter().any(|(.., parent)| parent.get() == entity);
+        let retextured = cache.textures != road.segment_textures;
+        // Slices depend on the whole road through its length, banking and so on, so once any
+        // segment has moved every slice is placed again, but only the moved segments are resliced
+        if !dirty && built && !retextured {
+            continue;
+        }
+        cache.textures.clone_from(&road.segment_textures);
+        let mut slices = cache.slices();
+        // Filled back in once the road has been built
+        stats_by_road.remove(&entity);
+
+        // Indices are u32, so the whole road has to fit in that many vertices. Past that, every
+        // segment is sliced evenly at the most detail that fits instead
+        let stride = profile_vertices(&config.profile_kind, config.lanes, 0.0).len();
+        let cap_ring_total = if config.closed || !profile_is_closed(&config.profile_kind) {
+            0
+        } else {
+            2 * cap_ring_count(config.cap_style)
+        };
+        let limit = max_slices(stride, cap_ring_total);
+        let clamped_detail = (slices.len() > limit).then(|| {
+            // Segments share their end slices, so n segments take n * (detail - 1) + 1 slices
+            let detail = (limit.saturating_sub(1) / cache.segments.len().max(1) + 1).max(2);
+            slices = segments(&control_points)
+                .enumerate()
+                .flat_map(|(i, segment)| {
+                    let first_slice = if i == 0 { 0 } else { 1 };
+                    (first_slice..detail).map(move |j| (segment, j as f32 / (detail - 1) as f32))
+                })
+                .collect();
+            detail
+        });
+        // Only report when the clamping changes, rather than on every rebuild
+        if clamped_roads.insert(entity, clamped_detail) != Some(clamped_detail) {
+            if let Some(detail) = clamped_detail {
+                error!("Road needs more than {} vertices, so its detail has been clamped to {detail} slices a segment", u32::MAX);
+            }
+        }
+
+        if !slices.is_empty() {
+            // Plain references rather than system params, so slices can be shared between threads
+            let config: &Config = &config;
+            let frames = slice_frames(&slices, config, &meshes, &terrain_q);
+            let slice_textures = slice_textures(&slices, &road.segment_textures);
+
+            let mut surfaces = surface_q
+                .iter()
+                .filter(|(.., parent)| parent.get() == entity)
+                .map(|(surface, RoadSurface { group, texture }, handle, _)| {
+                    ((*group, texture.as_deref()), (surface, handle))
+                })
+                .collect::<HashMap<_, _>>();
+            let (mut inverted, mut weld_counts) = (false, (0, 0));
+            let mut stats = RoadStats {
+                roads: 1,
+                segments: cache.segments.len(),
+                bounds_min: Vec3::splat(f32::INFINITY),
+                bounds_max: Vec3::splat(f32::NEG_INFINITY),
+                ..default()
+            };
+            stats.curve_length = extrude_surfaces(
+                &slices,
+                &frames,
+                config,
+                &slice_textures,
+                // Refilled for every surface rather than collected afresh, so rebuilding doesn't
+                // churn through new buffers each frame
+                &mut mesh_buffers,
+                |group, texture, mesh_data| {
+                    inverted |= mesh_data.looks_inverted();
+
+                    if config.normal_mode == NormalMode::FlatPerTriangle {
+                        mesh_data.flat_shade();
+                    }
+                    // Coplanar neighbours still share normals after flat shading, so welding can
+                    // merge some of them back together
+                    if config.weld {
+                        weld_counts.0 += mesh_data.vertex_count();
+                        mesh_data.weld(WELD_EPSILON);
+                        weld_counts.1 += mesh_data.vertex_count();
+                    }
+
+                    mesh_data.prefer_u16 = config.prefer_u16;
+                    for &position in &mesh_data.positions {
+                        stats.bounds_min = stats.bounds_min.min(position);
+                        stats.bounds_max = stats.bounds_max.max(position);
+                    }
+                    stats.vertices += mesh_data.vertex_count();
+
+                    if let Some((surface, mesh_handle)) = surfaces.remove(&(group, texture)) {
+                        let mesh = meshes.get_mut(mesh_handle).unwrap();
+                        mesh_data.apply_to(mesh);
+                        stats.triangles += triangle_count(mesh);
+                        // Bevy only computes bounds for entities that don't have any yet, so a mesh
+                        // edited in place would otherwise keep culling against its first shape
+                        if let Some(aabb) = mesh.compute_aabb() {
+                            commands.entity(surface).insert(aabb);
+                        }
+                    } else {
+                        let mesh = mesh_data.clone().into_mesh();
+                        stats.triangles += triangle_count(&mesh);
+                        let handle = meshes.add(mesh);
+
+                        // The material is added by `update_material`
+                        commands
+                            .entity(entity)
+                            .insert(Generated)
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    RoadSurface {
+                                        group,
+                                        texture: texture.map(String::from),
+                                    },
+                                    PbrBundle {
+                                        mesh: handle,
+                                        ..default()
+                                    },
+                                ));
+                            });
+                    }
+                },
+            );
+            // Surfaces the current profile doesn't have, such as curbs after switching to a tube, or
+            // textures no segment uses any more
+            for (surface, _) in surfaces.into_values() {
+                commands.entity(surface).despawn_recursive();
+            }
+            stats_by_road.insert(entity, stats);
+
+            // Only warn when a road turns inside out, rather than on every rebuild
+            if inverted_roads.insert(entity, inverted) != Some(inverted) && inverted {
+                warn!("Road faces wind against their normals and will render inside out, toggle flip_faces to correct it");
+            }
+            // Only report when the savings change, rather than on every rebuild
+            if config.weld && last_weld_counts.insert(entity, weld_counts) != Some(weld_counts) {
+                info!(
+                    "Welded {} vertices down to {}",
+                    weld_counts.0, weld_counts.1
+                );
+            }
+        }
+    }
+    let totals = stats_by_road
+        .values()
+        .fold(RoadStats::default(), |totals, stats| totals.merge(*stats));
+    road_stats.set_if_neq(totals);
+}
+
+/// Texture from `segment_textures` for each of `slices`, or `None` for the default
+fn slice_textures<'a>(
+    slices: &[([Vec3; 4], f32)],
+    segment_textures: &'a [Option<String>],
+) -> Vec<Option<&'a str>> {
+    slices
+        .iter()
+        .scan((0, None), |(segment, previous), (points, _)| {
+            if previous.map_or(false, |previous| previous != *points) {
+                *segment += 1;
+            }
+            *previous = Some(*points);
+            Some(*segment)
+        })
+        .map(|segment| {
+            segment_textures
+                .get(segment)
+                .and_then(|texture| texture.as_deref())
+                .filter(|texture| !texture.is_empty())
+        })
+        .collect()
+}
+
+/// Extrudes each profile surface along `slices`, returning the length of the road
+fn extrude_surfaces<'a>(
+    slices: &[([Vec3; 4], f32)],
+    frames: &[Mat4],
+    config: &Config,
+    slice_textures: &[Option<&'a str>],
+    mesh_data: &mut MeshData,
+    mut surface: impl FnMut(SurfaceGroup, Option<&'a str>, &mut MeshData),
+) -> f32 {
+    // Accumulate the distance travelled along the curve so V follows arc length rather than t
+    let distances = path_distances(frames.iter().map(|frame| frame.w_axis.truncate()));
+    let length = distances.last().copied().unwrap_or_default();
+
+    // Frame and local vertices of the profile at a slice
+    let slice_profile = |i: usize| {
+        let distance = distances[i];
+        // Twist rolls about the tangent (local Z) just like banking does, so the two add up
+        // and the order they're applied in makes no difference. Both roll the profile
+        // before it's placed, so its UVs spin along with it
+        let twist = config.twist_turns * TAU * distance / length.max(f32::EPSILON);
+        let frame = frames[i] * Mat4::from_rotation_z(twist);
+        let v = distance * config.texture_tiling;
+        let width = width_at(&config.width_profile, distance / length.max(f32::EPSILON));
+        let mut local_vertices = profile_vertices(&config.profile_kind, config.lanes, v);
+        if config.uv_layout == UvLayout::Atlas {
+            apply_atlas(&config.profile_kind, config.lanes, &mut local_vertices);
+        }
+        let local_vertices = local_vertices
+            .into_iter()
+            .map(|mut local_vertex| {
+                // Stretch to the width and scale here. UVs are left alone so the texture
+                // narrows with the road instead of being cropped
+                let scale = (config.profile_scale.max(Vec2::splat(MIN_WIDTH))
+                    * Vec2::new(width, 1.0))
+                .extend(1.0);
+                local_vertex.point = local_vertex.point * scale + Vec3::X * config.lateral_offset;
+                // Normals take the inverse transpose of the scale, which for a scale is
+                // its reciprocal, so they stay perpendicular to the stretched faces
+                local_vertex.normal = (local_vertex.normal / scale).normalize();
+                local_vertex
+            })
+            .collect::<Vec<_>>();
+        (frame, local_vertices)
+    };
+    // Map these local points to world points by adding them to the curve point
+    let place = |frame: Mat4, local_vertices: Vec<Vertex>| {
+        local_vertices
+            .into_iter()
+            .map(|mut local_vertex| {
+                local_vertex.point = frame.transform_point3(local_vertex.point);
+                local_vertex.normal = frame.transform_vector3(local_vertex.normal);
+                local_vertex
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut rings = map_slices(frames.len(), |i| {
+        let (frame, local_vertices) = slice_profile(i);
+        place(frame, local_vertices)
+    });
+    // Slice that each ring of vertices takes its curvature colour from
+    let mut ring_slices = (0..slices.len()).collect::<Vec<_>>();
+    if !config.closed && profile_is_closed(&config.profile_kind) {
+        // Caps carry on from the end slices like extra slices, so they share their faces'
+        // layout. The start cap's rings run out from the first slice, so they're reversed
+        // to run along the road like the rest
+        let depth = profile_top(&config.profile_kind) * config.profile_scale.y;
+        let (first_frame, first_vertices) = slice_profile(0);
+        let (last_frame, last_vertices) = slice_profile(frames.len() - 1);
+        let start_cap = cap_rings(
+            &first_vertices,
+            Vec3::Z,
+            config.cap_style,
+            depth,
+            -config.texture_tiling,
+        );
+        let end_cap = cap_rings(
+            &last_vertices,
+            Vec3::NEG_Z,
+            config.cap_style,
+            depth,
+            config.texture_tiling,
+        );
+        ring_slices = std::iter::repeat(0)
+            .take(start_cap.len())
+            .chain(ring_slices)
+            .chain(std::iter::repeat(slices.len() - 1).take(end_cap.len()))
+            .collect();
+        rings = start_cap
+            .into_iter()
+            .rev()
+            .map(|ring| place(first_frame, ring))
+            .chain(rings)
+            .chain(end_cap.into_iter().map(|ring| place(last_frame, ring)))
+            .collect();
+    }
+    // Baked per ring, since it only looks across the profile and at the bend it sits in
+    let occlusion = if config.bake_ao {
+        rings
+            .iter()
+            .zip(&ring_slices)
+            .flat_map(|(ring, &slice)| {
+                let ([a, b, c, d], t) = slices[slice];
+                ring_occlusion(ring, cubic_bezier_curvature_vector(a, b, c, d, t))
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+    let vertices = rings.into_iter().flatten().collect::<Vec<_>>();
+
+    let slice_count = ring_slices.len();
+    let profile_stride = vertices.len() / slice_count;
+    let ring_textures = ring_slices
+        .iter()
+        .map(|&slice| slice_textures.get(slice).copied().flatten())
+        .collect::<Vec<_>>();
+    for (group, edges) in profile_surfaces(&config.profile_kind, config.lanes) {
+        // Each surface only keeps the profile vertices its own faces use
+        let (used, edges) = surface_vertices(&edges);
+        let stride = used.len();
+        // Only the driving surface is textured, so the rest is one run the length of the road
+        let parts = if group == SurfaceGroup::Road {
+            texture_runs(&ring_textures)
+        } else {
+            texture_runs(&vec![None; slice_count])
+        };
+        for (texture, runs) in parts {
+            let surface_indices = runs
+                .iter()
+                .flat_map(|run| run.clone())
+                .flat_map(|ring| {
+                    used.iter()
+                        .map(move |&i| ring * profile_stride + i as usize)
+                })
+                .collect::<Vec<_>>();
+            let surface_vertices = surface_indices
+                .iter()
+                .map(|&i| &vertices[i])
+                .collect::<Vec<_>>();
+
+            mesh_data.clear();
+            // Each run's rings follow on from the last run's in the mesh
+            let run_starts = runs.iter().scan(0, |start, run| {
+                let run_start = *start;
+                *start += run.len() * stride;
+                Some((run_start as u32, run.len()))
+            });
+            mesh_data.topology = match config.topology {
+                Topology::TriangleList => {
+                    let base_tris = slice_triangles(&edges, stride);
+                    for (run_start, run_length) in run_starts {
+                        for i in 0..(run_length - 1) {
+                            for j in &base_tris {
+                                mesh_data.indices.push(j + run_start + (i * stride) as u32);
+                            }
+                        }
+                    }
+                    PrimitiveTopology::TriangleList
+                }
+                Topology::TriangleStrip => {
+                    for (run_start, run_length) in run_starts {
+                        triangle_strips(
+                            &edges,
+                            stride,
+                            run_length,
+                            run_start,
+                            &mut mesh_data.indices,
+                        );
+                    }
+                    PrimitiveTopology::TriangleStrip
+                }
+            };
+            mesh_data
+                .positions
+                .extend(surface_vertices.iter().map(|v| v.point));
+            mesh_data
+                .normals
+                .extend(surface_vertices.iter().map(|v| v.normal));
+            mesh_data.uvs.extend(surface_vertices.iter().map(|v| v.uv));
+            if config.curvature_coloring || config.bake_ao {
+                let colors = mesh_data.colors.get_or_insert_with(Vec::new);
+                colors.extend(surface_indices.iter().map(|&i| {
+                    // Every vertex in a slice shares the curvature colour of that slice
+                    let color = if config.curvature_coloring {
+                        let ([a, b, c, d], t) = slices[ring_slices[i / profile_stride]];
+                        curvature_color(cubic_bezier_curvature(a, b, c, d, t))
+                    } else {
+                        Color::WHITE
+                    };
+                    let [red, green, blue, alpha] = color.as_linear_rgba_f32();
+                    let light = occlusion.get(i).copied().unwrap_or(1.0);
+                    [red * light, green * light, blue * light, alpha]
+                }));
+            } else {
+                mesh_data.colors = None;
+            }
+
+            if config.flip_faces {
+                mesh_data.flip_faces();
+            }
+
+            surface(group, texture, mesh_data);
+        }
+    }
+    length
+}
+
+/// Runs of rings for each texture in `ring_textures`, each including the ring before
+fn texture_runs<'a>(
+    ring_textures: &[Option<&'a str>],
+) -> Vec<(Option<&'a str>, Vec<Range<usize>>)> {
+    let mut parts: Vec<(Option<&str>, Vec<Range<usize>>)> = vec![];
+    let mut start = 0;
+    for ring in 1..=ring_textures.len() {
+        if ring < ring_textures.len() && ring_textures[ring] == ring_textures[start] {
+            continue;
+        }
+        let texture = ring_textures[start];
+        let run = start.saturating_sub(1)..ring;
+        match parts
+            .iter_mut()
+            .find(|(part_texture, _)| *part_texture == texture)
+        {
+            Some((_, runs)) => runs.push(run),
+            None => parts.push((texture, vec![run])),
+        }
+        start = ring;
+    }
+    parts
+}
+
+/// Segment and `t` of every slice along the curve
+fn road_slices(control_points: &[Vec3], detail: usize, config: &Config) -> Vec<([Vec3; 4], f32)> {
+    segments(control_points)
+        .enumerate()
+        .flat_map(|(i, segment)| {
+            segment_parameters(segment, i, detail, config.adaptive, config)
+                .into_iter()
+                .map(move |t| (segment, t))
+        })
+        .collect()
+}
+
+/// `t` of every slice in segment `index`, skipping the first after the first segment
+fn segment_parameters(
+    [a, b, c, d]: [Vec3; 4],
+    index: usize,
+    detail: usize,
+    adaptive: bool,
+    config: &Config,
+) -> Vec<f32> {
+    let parameters = if adaptive {
+        subdivision_parameters(
+            a,
+            b,
+            c,
+            d,
+            config.max_chord_error,
+            config.min_slices,
+            config.max_slices,
+        )
+    } else {
+        let detail = match config.density_mode {
+            DensityMode::Fixed => detail,
+            // Spans rather than slices, so a segment gets one more slice than its length asks for
+            DensityMode::PerLength(density) => {
+                (curve_length(&[a, b, c, d]) * density.max(0.0)).ceil() as usize + 1
+            }
+        }
+        .max(2);
+        (0..detail)
+            .map(|j| j as f32 / (detail as f32 - 1.0))
+            .collect()
+    };
+    let first_slice = if index == 0 { 0 } else { 1 };
+    parameters.into_iter().skip(first_slice).collect()
+}
+
+/// Slice parameters of each segment as of the last rebuild, to reslice only moved ones
+#[derive(Default)]
+struct SegmentCache {
+    /// `Road.detail` the segments were sliced with
+    detail: usize,
+    /// Each segment, its slice parameters, and whether they were sliced at the preview detail
+    segments: Vec<([Vec3; 4], Vec<f32>, bool)>,
+    /// `Road.segment_textures` the road was last built with
+    textures: Vec<Option<String>>,
+}
+
+impl SegmentCache {
+    /// Reslices the segments that moved or are new, returning whether there were any
+    fn update(
+        &mut self,
+        control_points: &[Vec3],
+        detail: usize,
+        preview: Option<usize>,
+        config: &Config,
+    ) -> bool {
+        if detail != self.detail {
+            self.detail = detail;
+            self.segments.clear();
+        }
+        let segments = segments(control_points).collect::<Vec<_>>();
+        let mut dirty = segments.len() != self.segments.len();
+        self.segments.truncate(segments.len());
+        for (i, segment) in segments.into_iter().enumerate() {
+            // Full detail segments are kept while previewing too
+            if self
+                .segments
+                .get(i)
+                .map_or(false, |(cached, _, previewed)| {
+                    *cached == segment && (!previewed || preview.is_some())
+                })
+            {
+                continue;
+            }
+            dirty = true;
+            let parameters = match preview {
+                Some(preview) => segment_parameters(segment, i, preview.max(2), false, config),
+                None => segment_parameters(segment, i, detail, config.adaptive, config),
+            };
+            let entry = (segment, parameters, preview.is_some());
+            if i < self.segments.len() {
+                self.segments[i] = entry;
+            } else {
+                self.segments.push(entry);
+            }
+        }
+        dirty
+    }
+
+    /// Segment and `t` of every slice along the road
+    fn slices(&self) -> Vec<([Vec3; 4], f32)> {
+        self.segments
+            .iter()
+            .flat_map(|(segment, parameters, _)| parameters.iter().map(|&t| (*segment, t)))
+            .collect()
+    }
+}
+
+/// Distance to each of `points` from the first, following straight lines between them
+fn path_distances(points: impl IntoIterator<Item = Vec3>) -> Vec<f32> {
+    points
+        .into_iter()
+        .scan((0.0, None), |(distance, previous_point), point| {
+            if let Some(previous_point) = *previous_point {
+                *distance += point.distance(previous_point);
+            }
+            *previous_point = Some(point);
+            Some(*distance)
+        })
+        .collect()
+}
+
+/// Frame of each slice, banked and conformed to the terrain, before any twist
+fn slice_frames(
+    slices: &[([Vec3; 4], f32)],
+    config: &Config,
+    meshes: &Assets<Mesh>,
+    terrain_q: &Query<(&Handle<Mesh>, &GlobalTransform), With<Terrain>>,
+) -> Vec<Mat4> {
+    // Roll each slice about the tangent to bank it into the bend
+    let banks = bank_angles(slices, config.auto_bank);
+    // Elevation keyframes are placed by distance along the plan, before any height is added
+    let distances = path_distances(
+        slices
+            .iter()
+            .map(|&([a, b, c, d], t)| cubic_bezier(a, b, c, d, t)),
+    );
+    let length = distances.last().copied().unwrap_or_default();
+    let mut rotations = curve_frames(slices, config.frame_mode, config.up);
+    if config.smooth_joins > 0.0 {
+        smooth_joins(slices, &mut rotations, &distances, config.smooth_joins);
+    }
+    map_slices(slices.len(), |i| {
+        let (position, rotation) = rotations[i];
+        let frame = Transform::from_translation(position)
+            .with_rotation(rotation)
+            .compute_matrix();
+        let frame =
+            elevate_frame(frame, config, distances[i], length) * Mat4::from_rotation_z(banks[i]);
+        conform_frame(frame, config, meshes, terrain_q)
+    })
+}
+
+/// Eases sharp turns of the frames at segment joins over `window` either side
+fn smooth_joins(
+    slices: &[([Vec3; 4], f32)],
+    frames: &mut [(Vec3, Quat)],
+    distances: &[f32],
+    window: f32,
+) {
+    // A segment's first slice is skipped, so each join is the last slice of the segment before it
+    let joins = (0..slices.len().saturating_sub(1)).filter(|&i| slices[i].0 != slices[i + 1].0);
+    for join in joins.collect::<Vec<_>>() {
+        let [a, b, c, d] = slices[join + 1].0;
+        let incoming = frames[join].1 * Vec3::NEG_Z;
+        let outgoing = cubic_bezier_direction(a, b, c, d, 0.0);
+        let half_turn = Quat::IDENTITY.slerp(Quat::from_rotation_arc(incoming, outgoing), 0.5);
+        for (i, (_, rotation)) in frames.iter_mut().enumerate() {
+            let weight = 1.0 - (distances[i] - distances[join]).abs() / window;
+            if weight <= 0.0 {
+                continue;
+            }
+            // Slices after the join have already taken the whole turn, so they back off half of it
+            let turn = if i <= join {
+                half_turn
+            } else {
+                half_turn.inverse()
+            };
+            *rotation = Quat::IDENTITY.slerp(turn, weight) * *rotation;
+        }
+    }
+}
+
+/// Distance between the steps that carry a parallel transported frame out to a single point
+const TRANSPORT_STEP: f32 = 0.05;
+
+/// `f` of every slice index in order, across threads with the `rayon` feature
+#[cfg(feature = "rayon")]
+fn map_slices<T: Send>(count: usize, f: impl Fn(usize) -> T + Send + Sync) -> Vec<T> {
+    (0..count).into_par_iter().map(f).collect()
+}
+
+/// `f` applied to every slice index in `0..count`, in order
+#[cfg(not(feature = "rayon"))]
+fn map_slices<T>(count: usize, f: impl Fn(usize) -> T) -> Vec<T> {
+    (0..count).map(f).collect()
+}
+
+/// Narrowest the profile can be squeezed to without collapsing its normals
+const MIN_WIDTH: f32 = 0.01;
+
+/// Width at `position` (0..1 along the road), interpolated linearly between keyframes
+fn width_at(keyframes: &[WidthKeyframe], position: f32) -> f32 {
+    let width = match keyframes
+        .iter()
+        .position(|keyframe| keyframe.position > position)
+    {
+        None => keyframes.last().map_or(1.0, |keyframe| keyframe.width),
+        Some(0) => keyframes[0].width,
+        Some(i) => {
+            let (before, after) = (&keyframes[i - 1], &keyframes[i]);
+            let fraction = (position - before.position) / (after.position - before.position);
+            before.width + (after.width - before.width) * fraction
+        }
+    };
+    width.max(MIN_WIDTH)
+}
+
+/// One strip per profile edge with the same triangles as [`slice_triangles`], joined
+fn triangle_strips(
+    edges: &[[u32; 2]],
+    stride: usize,
+    slice_count: usize,
+    first_vertex: u32,
+    indices: &mut Vec<u32>,
+) {
+    if slice_count == 0 {
+        return;
+    }
+    let edges = edges
+        .iter()
+        .map(|edge| edge.map(|i| i + first_vertex))
+        .collect::<Vec<_>>();
+    for [a, b] in edges {
+        // Every strip starts on `b`. Strips have an even length, so after the first this lands
+        // every strip on an odd index
+        match indices.last() {
+            Some(&last) => indices.extend([last, b]),
+            None => indices.push(b),
+        }
+        indices.extend(
+            (0..slice_count as u32).flat_map(|slice| [b, a].map(|i| i + slice * stride as u32)),
+        );
+    }
+}
+
+/// Most slices of `stride` vertices, plus `cap_rings` rings, whose vertices fit a u32
+fn max_slices(stride: usize, cap_rings: usize) -> usize {
+    (u32::MAX as usize / stride.max(1)).saturating_sub(cap_rings)
+}
+
+/// Vertices closer together than this (in every attribute) are merged when welding
+const WELD_EPSILON: f32 = 1e-4;
+
+/// Steepest the road will bank, in radians
+const MAX_BANK_ANGLE: f32 = PI / 3.0;
+/// Number of neighbouring slices either side that bank angles are averaged over
+const BANK_SMOOTHING: usize = 2;
+
+/// Roll about the tangent for a bend, raising the outside edge
+fn bank_angle(curvature: f32, auto_bank: f32) -> f32 {
+    (curvature * auto_bank).clamp(-MAX_BANK_ANGLE, MAX_BANK_ANGLE)
+}
+
+/// Bank angle of each slice, averaged over its neighbours to blend across joins
+fn bank_angles(slices: &[([Vec3; 4], f32)], auto_bank: f32) -> Vec<f32> {
+    let angles = slices
+        .iter()
+        .map(|&([a, b, c, d], t)| bank_angle(cubic_bezier_curvature(a, b, c, d, t), auto_bank))
+        .collect::<Vec<_>>();
+    (0..angles.len())
+        .map(|i| {
+            let window = &angles
+                [i.saturating_sub(BANK_SMOOTHING)..(i + BANK_SMOOTHING + 1).min(angles.len())];
+            window.iter().sum::<f32>() / window.len() as f32
+        })
+        .collect()
+}
+
+/// Frame at `distance` along the curve, banked the same way as the slice there
+fn banked_frame_at_distance(
+    table: &ArcLengthTable,
+    distance: f32,
+    config: &Config,
+) -> Option<Mat4> {
+    let ([a, b, c, d], t) = table.segment_at_distance(distance)?;
+    let bank = bank_angle(cubic_bezier_curvature(a, b, c, d, t), config.auto_bank);
+    // A transported frame depends on the whole curve before it, so it's stepped out from the start
+    let samples = match config.frame_mode {
+        FrameMode::ParallelTransport => {
+            let steps = (distance / TRANSPORT_STEP).ceil().max(1.0) as usize;
+            (0..=steps)
+                .filter_map(|i| table.segment_at_distance(distance * i as f32 / steps as f32))
+                .collect()
+        }
+        FrameMode::WorldUp | FrameMode::FixedUp(_) => vec![([a, b, c, d], t)],
+    };
+    let (position, rotation) = *curve_frames(&samples, config.frame_mode, config.up).last()?;
+    let frame = Mat4::from_rotation_translation(rotation, position);
+    Some(elevate_frame(frame, config, distance, table.length()) * Mat4::from_rotation_z(bank))
+}
+
+/// Added height and its rate of change at `position` (0..1 along the road)
+fn elevation_at(keyframes: &[ElevationKeyframe], position: f32) -> (f32, f32) {
+    match keyframes
+        .iter()
+        .position(|keyframe| keyframe.position > position)
+    {
+        None => (
+            keyframes.last().map_or(0.0, |keyframe| keyframe.height),
+            0.0,
+        ),
+        Some(0) => (keyframes[0].height, 0.0),
+        Some(i) => {
+            let (before, after) = (&keyframes[i - 1], &keyframes[i]);
+            let span = after.position - before.position;
+            let fraction = (position - before.position) / span;
+            let rise = after.height - before.height;
+            // Smoothstep, which levels off at both keyframes
+            let eased = fraction * fraction * (3.0 - 2.0 * fraction);
+            let slope = 6.0 * fraction * (1.0 - fraction) / span;
+            (before.height + rise * eased, rise * slope)
+        }
+    }
+}
+
+/// Unbanked `frame` raised by `Config.elevation` and pitched to its grade, still orthonormal
+fn elevate_frame(frame: Mat4, config: &Config, distance: f32, length: f32) -> Mat4 {
+    if config.elevation.is_empty() {
+        return frame;
+    }
+    let up = config.up.try_normalize().unwrap_or(Vec3::Y);
+    let (height, slope) = elevation_at(&config.elevation, distance / length.max(f32::EPSILON));
+    // Slope is per fraction of the road, so per unit of distance it's spread over the length
+    let grade = slope / length.max(f32::EPSILON);
+    let z = (frame.z_axis.truncate() - up * grade).normalize();
+    let y = frame.y_axis.truncate();
+    let y = (y - z * y.dot(z)).normalize();
+    let x = y.cross(z);
+    Mat4::from_cols(
+        Vec4::from((x, 0.0)),
+        Vec4::from((y, 0.0)),
+        Vec4::from((z, 0.0)),
+        frame.w_axis + Vec4::from((up * height, 0.0)),
+    )
+}
+
+/// How far above the curve the terrain ray starts
+const TERRAIN_RAY_HEIGHT: f32 = 100.0;
+
+/// Moves `frame` onto the `Terrain` under it, following its normal with `align_to_terrain`
+fn conform_frame(
+    frame: Mat4,
+    config: &Config,
+    meshes: &Assets<Mesh>,
+    terrain_q: &Query<(&Handle<Mesh>, &GlobalTransform), With<Terrain>>,
+) -> Mat4 {
+    if !config.conform_to_terrain {
+        return frame;
+    }
+    let up = config.up.try_normalize().unwrap_or(Vec3::Y);
+    let ray = Ray3d::new(frame.w_axis.truncate() + up * TERRAIN_RAY_HEIGHT, -up);
+    let Some(hit) = terrain_q
+        .iter()
+        .filter_map(|(handle, transform)| {
+            let mesh = meshes.get(handle)?;
+            ray_intersection_over_mesh(mesh, &transform.compute_matrix(), &ray, Backfaces::Cull)
+        })
+        .min_by(|x, y| x.distance().total_cmp(&y.distance()))
+    else {
+        return frame;
+    };
+    let mut conformed = frame;
+    conformed.w_axis = Vec4::from((hit.position() + up * config.terrain_clearance, 1.0));
+    if config.align_to_terrain {
+        let y = hit.normal();
+        // A cliff face runs along the curve, so there's no sideways direction to tilt about
+        if let Some(x) = y.cross(frame.z_axis.truncate()).try_normalize() {
+            conformed.x_axis = Vec4::from((x, 0.0));
+            conformed.y_axis = Vec4::from((y, 0.0));
+            conformed.z_axis = Vec4::from((x.cross(y), 0.0));
+        }
+    }
+    conformed
+}
+
+fn follow_path(
+    time: Res<Time>,
+    config: Res<Config>,
+    meshes: Res<Assets<Mesh>>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    terrain_q: Query<(&Handle<Mesh>, &GlobalTransform), With<Terrain>>,
+    mut follower_q: Query<(&mut PathFollower, &mut Transform, &Parent), Without<ControlPoint>>,
+) {
+    for (mut follower, mut transform, road) in follower_q.iter_mut() {
+        let table = ArcLengthTable::new(&curve_points(&point_q, road.get(), &config));
+        let length = table.length();
+        if length <= 0.0 {
+            continue;
+        }
+        let distance = follower.distance + follower.speed * time.delta_seconds();
+        follower.distance = if config.closed {
+            distance.rem_euclid(length)
+        } else if !(0.0..=length).contains(&distance) {
+            // Turn around at either end of an open road
+            follower.speed = -follower.speed;
+            distance.clamp(0.0, length)
+        } else {
+            distance
+        };
+
+        if let Some(frame) = banked_frame_at_distance(&table, follower.distance, &config) {
+            let frame = conform_frame(frame, &config, &meshes, &terrain_q);
+            // The frame's local Z points back along the curve, which is Bevy's forward (-Z) facing
+            // along the tangent. Ping-ponging followers turn around to face the way they travel
+            let facing = if follower.speed < 0.0 && !config.closed {
+                Mat4::from_rotation_y(PI)
+            } else {
+                Mat4::IDENTITY
+            };
+            *transform = Transform::from_matrix(
+                frame * Mat4::from_translation(Vec3::Y * ROAD_SURFACE_HEIGHT) * facing,
+            );
+        }
+    }
+}
+
+/// Meshes created or modified since `mesh_events` was last read
+fn changed_meshes(mesh_events: &mut EventReader<AssetEvent<Mesh>>) -> Vec<Handle<Mesh>> {
+    mesh_events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                Some(handle.clone())
+            }
+            AssetEvent::Removed { .. } => None,
+        })
+        .collect()
+}
+
+/// Whether Ctrl or Shift is held, so bare-key shortcuts don't fire as part of a chord
+fn modifier_held(keys: &Input<KeyCode>) -> bool {
+    keys.any_pressed([
+        KeyCode::LControl,
+        KeyCode::RControl,
+        KeyCode::LShift,
+        KeyCode::RShift,
+    ])
+}
+
+/// How much room to leave around the roads when framing them, as a multiple of their size
+const FRAMING_MARGIN: f32 = 1.2;
+
+/// Pressing F frames all the generated roads in the camera
+fn frame_roads(
+    keys: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+    surface_q: Query<(&Aabb, &GlobalTransform), With<RoadSurface>>,
+    mut camera_q: Query<(&mut PanOrbitCamera, &Projection)>,
+) {
+    if !keys.just_pressed(KeyCode::F)
+        || modifier_held(&keys)
+        || contexts.ctx_mut().wants_keyboard_input()
+    {
+        return;
+    }
+    // Roads that haven't been generated yet have no bounds, and there's nothing to frame
+    let Some((min, max)) = road_bounds(&surface_q) else {
+        return;
+    };
+    let (center, radius) = ((min + max) / 2.0, (max - min).length() / 2.0);
+    for (mut camera, projection) in camera_q.iter_mut() {
+        let fov = match projection {
+            Projection::Perspective(perspective) => perspective.fov,
+            Projection::Orthographic(_) => PI / 4.0,
+        };
+        camera.focus = center;
+        camera.radius = radius * FRAMING_MARGIN / (fov / 2.0).sin();
+        camera.force_update = true;
+    }
+}
+
+/// World space bounds of every generated road surface, or `None` before there are any
+fn road_bounds(
+    surface_q: &Query<(&Aabb, &GlobalTransform), With<RoadSurface>>,
+) -> Option<(Vec3, Vec3)> {
+    surface_q
+        .iter()
+        .flat_map(|(aabb, transform)| {
+            let (center, half_extents) = (Vec3::from(aabb.center), Vec3::from(aabb.half_extents));
+            [center - half_extents, center + half_extents]
+                .map(|corner| transform.transform_point(corner))
+        })
+        .fold(None, |bounds: Option<(Vec3, Vec3)>, point| {
+            Some(bounds.map_or((point, point), |(min, max)| {
+                (min.min(point), max.max(point))
+            }))
+        })
+}
+
+/// Keys and camera `alpha` and `beta` for the top, front and side views
+const PRESET_VIEWS: [([KeyCode; 2], f32, f32); 3] = [
+    ([KeyCode::Numpad7, KeyCode::Key7], 0.0, FRAC_PI_2),
+    ([KeyCode::Numpad1, KeyCode::Key1], 0.0, 0.0),
+    ([KeyCode::Numpad3, KeyCode::Key3], FRAC_PI_2, 0.0),
+];
+
+/// Turns the camera to one of the `PRESET_VIEWS`, centred on the roads
+fn snap_camera_view(
+    keys: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+    config: Res<Config>,
+    surface_q: Query<(&Aabb, &GlobalTransform), With<RoadSurface>>,
+    mut camera_q: Query<&mut PanOrbitCamera>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    let Some(&(_, alpha, beta)) = PRESET_VIEWS
+        .iter()
+        .find(|(view_keys, ..)| keys.any_just_pressed(*view_keys))
+    else {
+        return;
+    };
+    let center = road_bounds(&surface_q).map(|(min, max)| (min + max) / 2.0);
+    for mut camera in camera_q.iter_mut() {
+        if let Some(center) = center {
+            camera.focus = center;
+        }
+        camera.target_alpha = alpha;
+        camera.target_beta = beta;
+        if !config.animate_views {
+            camera.alpha = alpha;
+            camera.beta = beta;
+        }
+        camera.force_update = true;
+    }
+}
+
+/// Gives each `RoadSurface` the material for `Config.material_mode`
+fn update_material(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut debug_materials: ResMut<Assets<UvDebugMaterial>>,
+    config: Res<Config>,
+    asset_server: Res<AssetServer>,
+    surface_q: Query<(Entity, Ref<RoadSurface>)>,
+    mut debug_material: Local<Option<Handle<UvDebugMaterial>>>,
+) {
+    for (entity, surface) in surface_q.iter() {
+        if !config.is_changed() && !surface.is_added() {
+            continue;
+        }
+        let mut road_commands = commands.entity(entity);
+        match config.material_mode {
+            MaterialMode::Textured | MaterialMode::Wireframe => {
+                road_commands
+                    .remove::<Handle<UvDebugMaterial>>()
+                    .insert(materials.add(road_material(&config, &asset_server, &surface)));
+            }
+            // All surfaces share the one debug material so it can be tweaked in one place
+            MaterialMode::UvDebug => {
+                let debug_material = debug_material
+                    .get_or_insert_with(|| debug_materials.add(UvDebugMaterial::default()))
+                    .clone();
+                road_commands
+                    .remove::<Handle<StandardMaterial>>()
+                    .insert(debug_material);
+            }
+        }
+        if config.material_mode == MaterialMode::Wireframe {
+            road_commands.insert(Wireframe);
+        } else {
+            road_commands.remove::<Wireframe>();
+        }
+    }
+}
+
+/// Sets every road texture's sampler to the configured wrap modes as it loads
+fn apply_texture_wrap(
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut images: ResMut<Assets<Image>>,
+    materials: Res<Assets<StandardMaterial>>,
+    config: Res<Config>,
+    surface_q: Query<Ref<Handle<StandardMaterial>>, With<RoadSurface>>,
+) {
+    let image_loaded = image_events.iter().any(|event| {
+        matches!(
+            event,
+            AssetEvent::Created { .. } | AssetEvent::Modified { .. }
+        )
+    });
+    if !config.is_changed() && !image_loaded && !surface_q.iter().any(|handle| handle.is_changed())
+    {
+        return;
+    }
+    let (address_mode_u, address_mode_v) = (
+        config.texture_wrap_u.address_mode(),
+        config.texture_wrap_v.address_mode(),
+    );
+    let textures = surface_q
+        .iter()
+        .filter_map(|handle| materials.get(&handle)?.base_color_texture.clone())
+        .collect::<HashSet<_>>();
+    for texture in textures {
+        let Some(image) = images.get(&texture) else {
+            continue;
+        };
+        // Only touch images that need it, since modifying one re-uploads it
+        if matches!(
+            &image.sampler_descriptor,
+            ImageSampler::Descriptor(sampler)
+                if sampler.address_mode_u == address_mode_u && sampler.address_mode_v == address_mode_v
+        ) {
+            continue;
+        }
+        if let Some(image) = images.get_mut(&texture) {
+            image.sampler_descriptor = ImageSampler::Descriptor(SamplerDescriptor {
+                address_mode_u,
+                address_mode_v,
+                ..ImageSampler::linear_descriptor()
+            });
+        }
+    }
+}
+
+/// Road surface texture used when `Config.texture_path` isn't set
+const DEFAULT_ROAD_TEXTURE: &str = "road.png";
+
+fn road_material(
+    config: &Config,
+    asset_server: &AssetServer,
+    surface: &RoadSurface,
+) -> StandardMaterial {
+    let mut material = if config.curvature_coloring {
+        // StandardMaterial multiplies the base colour by the mesh's vertex colours
+        StandardMaterial {
+            base_color: Color::WHITE,
+            ..default()
+        }
+    } else {
+        match surface.group {
+            SurfaceGroup::Road => StandardMaterial {
+                base_color_texture: Some(
+                    asset_server.load(
+                        surface
+                            .texture
+                            .as_deref()
+                            .or(config.texture_path.as_deref())
+                            .filter(|path| !path.is_empty())
+                            .unwrap_or(DEFAULT_ROAD_TEXTURE),
+                    ),
+                ),
+                ..default()
+            },
+            SurfaceGroup::Curb => config.curb_color.into(),
+            SurfaceGroup::Underside => config.underside_color.into(),
+        }
+    };
+    if config.double_sided {
+        // Back faces are lit with their normals flipped, rather than culled
+        material.double_sided = true;
+        material.cull_mode = None;
+    }
+    material
+}
+
+/// Furthest (in world units) that another part of the profile can be and still shade a vertex
+const AO_RADIUS: f32 = 0.3;
+/// Darkening of a fully occluded vertex, from 0 for none to 1 for black
+const AO_STRENGTH: f32 = 0.5;
+
+/// Rough ambient occlusion of each vertex of `ring` from concave corners and the `bend`
+fn ring_occlusion(ring: &[Vertex], bend: Vec3) -> Vec<f32> {
+    ring.iter()
+        .map(|vertex| {
+            let normal = vertex.normal.normalize_or_zero();
+            let concavity = ring
+                .iter()
+                .filter_map(|other| {
+                    let offset = other.point - vertex.point;
+                    // Split corners put several vertices at the same point
+                    let direction = offset.try_normalize()?;
+                    let closeness = 1.0 - offset.length() / AO_RADIUS;
+                    Some(normal.dot(direction).max(0.0) * closeness.max(0.0))
+                })
+                .fold(0.0, f32::max);
+            let bend = (normal.dot(bend) * AO_RADIUS).clamp(0.0, 1.0);
+            1.0 - AO_STRENGTH * concavity.max(bend)
+        })
+        .collect()
+}
+
+/// Curvature below this is treated as straight, so nearly straight sections stay neutral
+const CURVATURE_DEADZONE: f32 = 0.01;
+/// Curvature (beyond the deadzone) at which the colour ramp is fully saturated
+const CURVATURE_COLOR_RANGE: f32 = 1.0;
+
+/// Grey when straight, blending to red for left turns and blue for right turns
+fn curvature_color(curvature: f32) -> Color {
+    let neutral = Vec4::from(Color::GRAY.as_linear_rgba_f32());
+    let target = if curvature > 0.0 {
+        Color::RED
+    } else {
+        Color::BLUE
+    };
+    let strength = ((curvature.abs() - CURVATURE_DEADZONE) / CURVATURE_COLOR_RANGE).clamp(0.0, 1.0);
+    let color = neutral.lerp(Vec4::from(target.as_linear_rgba_f32()), strength);
+    Color::rgba_linear(color.x, color.y, color.z, color.w)
+}
+
+/// Keeps a trimesh collider on each road's surfaces, marking the road `ColliderBuilt`
+#[cfg(feature = "rapier")]
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_collider(
+    mut commands: Commands,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    meshes: Res<Assets<Mesh>>,
+    config: Res<Config>,
+    point_q: Query<(&ControlPoint, &Transform)>,
+    generated_q: Query<(Entity, Option<&ColliderBuilt>), With<Generated>>,
+    surface_q: Query<(
+        Entity,
+        &RoadSurface,
+        &Handle<Mesh>,
+        &Parent,
+        Option<&Collider>,
+    )>,
+    terrain_q: Query<(&Handle<Mesh>, &GlobalTransform), With<Terrain>>,
+    mut collider_buffers: Local<MeshData>,
+) {
+    let changed_meshes = changed_meshes(&mut mesh_events);
+
+    for (road, built) in generated_q.iter() {
+        let surfaces = surface_q
+            .iter()
+            .filter(|(.., parent, _)| parent.get() == road)
+            .collect::<Vec<_>>();
+        if !config.generate_collider {
+            for &(surface, .., collider) in &surfaces {
+                if collider.is_some() {
+                    commands.entity(surface).remove::<Collider>();
+                }
+            }
+            if built.is_some() {
+                commands.entity(road).remove::<ColliderBuilt>();
+            }
+            continue;
+        }
+        let stale = config.is_changed()
+            || built.is_none()
+            || surfaces
+                .iter()
+                .any(|(_, _, mesh_handle, ..)| changed_meshes.contains(*mesh_handle));
+        if !stale {
+            continue;
+        }
+
+        let Some(detail) = config.collider_detail else {
+            // A mesh that isn't loaded yet leaves the road stale, to be tried again next frame
+            let mut loaded = true;
+            for &(surface, _, mesh_handle, .., collider) in &surfaces {
+                let Some(mesh) = meshes.get(mesh_handle) else {
+                    loaded = false;
+                    continue;
+                };
+                let list_mesh;
+                let mesh = match (mesh.primitive_topology(), mesh.indices()) {
+                    (PrimitiveTopology::TriangleStrip, Some(_)) => {
+                        let mut copy = mesh.clone();
+                        copy.set_indices(triangle_list(mesh).map(Indices::U32));
+                        list_mesh = copy;
+                        &list_mesh
+                    }
+                    _ => mesh,
+                };
+                match Collider::from_bevy_mesh(mesh, &ComputedColliderShape::TriMesh) {
+                    Some(collider) => {
+                        commands.entity(surface).insert(collider);
+                    }
+                    None if collider.is_some() => {
+                        commands.entity(surface).remove::<Collider>();
+                    }
+                    None => {}
+                }
+            }
+            if loaded {
+                commands.entity(road).insert(ColliderBuilt);
+            } else if built.is_some() {
+                commands.entity(road).remove::<ColliderBuilt>();
+            }
+            continue;
+        };
+
+        // Segments share their end slices, so n segments take n * (detail - 1) + 1 slices
+        let control_points = curve_points(&point_q, road, &config);
+        let table = ArcLengthTable::new(&control_points);
+        let slice_count = segments(&control_points).count() * (detail.max(2) - 1) + 1;
+        let slices = (0..slice_count)
+            .filter_map(|i| {
+                table.segment_at_distance(table.length() * i as f32 / (slice_count - 1) as f32)
+            })
+            .collect::<Vec<_>>();
+        commands.entity(road).insert(ColliderBuilt);
+        if slices.len() < 2 {
+            continue;
+        }
+        let frames = slice_frames(&slices, &config, &meshes, &terrain_q);
+        // Textures don't matter to physics, so each group is extruded whole and its collider goes on
+        // the group's first surface, taking it off any others the driving surface is split into
+        for &(surface, .., collider) in &surfaces {
+            if collider.is_some() {
+                commands.entity(surface).remove::<Collider>();
+            }
+        }
+        extrude_surfaces(
+            &slices,
+            &frames,
+            &config,
+            &[],
+            &mut collider_buffers,
+            |group, _, mesh_data| {
+                let Some(&(surface, ..)) = surfaces
+                    .iter()
+                    .find(|(_, road_surface, ..)| road_surface.group == group)
+                else {
+                    return;
+                };
+                let indices = match mesh_data.topology {
+                    PrimitiveTopology::TriangleStrip => strip_to_list(&mesh_data.indices),
+                    _ => mesh_data.indices.clone(),
+                };
+                let triangles = indices
+                    .chunks_exact(3)
+                    .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+                    .collect();
+                commands
+                    .entity(surface)
+                    .insert(Collider::trimesh(mesh_data.positions.clone(), triangles));
+            },
+        );
+    }
+}
+
+fn draw_polyline(points: Vec<(Vec3, Color)>, painter: &mut ShapePainter) {
+    for window in points.windows(2) {
+        let (point_1, color_1) = window[0];
+        let (point_2, _) = window[1];
+        painter.color = color_1;
+        painter.line(point_1, point_2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bezier_mesh::curve::cubic_bezier_matrix;
+    use bezier_mesh::mesh::strip_to_list;
+
+    /// The settings the editor starts with, as far as extruding the road goes
+    pub(crate) fn road_config() -> Config {
+        Config {
+            texture_tiling: 1.0,
+            up: Vec3::Y,
+            lanes: 1,
+            profile_scale: Vec2::ONE,
+            ..default()
+        }
+    }
+
+    /// `count` slices evenly spaced in `t` along a gentle bend, and their frames
+    pub(crate) fn bend(count: usize) -> (Vec<([Vec3; 4], f32)>, Vec<Mat4>) {
+        let segment = [
+            Vec3::ZERO,
+            Vec3::new(0.0, 0.0, 2.0),
+            Vec3::new(2.0, 0.0, 4.0),
+            Vec3::new(4.0, 0.0, 4.0),
+        ];
+        let slices = (0..count)
+            .map(|i| (segment, i as f32 / (count - 1) as f32))
+            .collect::<Vec<_>>();
+        let frames = slices
+            .iter()
+            .map(|&([a, b, c, d], t)| cubic_bezier_matrix(a, b, c, d, t, Vec3::Y))
+            .collect();
+        (slices, frames)
+    }
+
+    /// Each surface of the road extruded with `config`, as its positions, UVs and triangle list
+    fn extrude(config: &Config, slice_count: usize) -> Vec<(Vec<Vec3>, Vec<Vec2>, Vec<u32>)> {
+        let (slices, frames) = bend(slice_count);
+        let mut surfaces = vec![];
+        extrude_surfaces(
+            &slices,
+            &frames,
+            config,
+            &[],
+            &mut MeshData::default(),
+            |_, _, mesh_data| {
+                let triangles = match mesh_data.topology {
+                    PrimitiveTopology::TriangleStrip => strip_to_list(&mesh_data.indices),
+                    _ => mesh_data.indices.clone(),
+                };
+                surfaces.push((
+                    mesh_data.positions.clone(),
+                    mesh_data.uvs.clone(),
+                    triangles,
+                ));
+            },
+        );
+        surfaces
+    }
+
+    /// Triangles of a list in sorted order, each rotated to start on its smallest index
+    fn canonical_triangles(indices: &[u32]) -> Vec<[u32; 3]> {
+        indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let mut triangle = [triangle[0], triangle[1], triangle[2]];
+                let first = (0..3).min_by_key(|&i| triangle[i]).unwrap();
+                triangle.rotate_left(first);
+                triangle
+            })
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn max_slices_keeps_indices_in_u32() {
+        let max_index = u32::MAX as u64;
+        // The road with caps, a one sided ribbon, a many sided tube, and strides that do and
+        // don't divide u32::MAX + 1
+        for (stride, cap_rings) in [(16, 16), (2, 0), (257, 4), (3, 0), (1 << 16, 0)] {
+            let slices = max_slices(stride, cap_rings) as u64;
+            let vertex_count = |slices: u64| (slices + cap_rings as u64) * stride as u64;
+            assert!(vertex_count(slices) - 1 <= max_index, "stride {stride}");
+            // The vertex count has to fit in a u32 as well, since offsets into the vertices are
+            // counted in them, so one more slice is too many
+            assert!(vertex_count(slices) <= max_index, "stride {stride}");
+            assert!(vertex_count(slices + 1) > max_index, "stride {stride}");
+        }
+    }
+
+    #[test]
+    fn no_triangle_wraps_round_in_u() {
+        let tube = |sides| ProfileKind::Tube { radius: 0.2, sides };
+        for profile_kind in [ProfileKind::Road, tube(3), tube(8)] {
+            let config = Config {
+                profile_kind: profile_kind.clone(),
+                ..road_config()
+            };
+            let surfaces = extrude(&config, 6);
+            let (min, max) = surfaces
+                .iter()
+                .flat_map(|(_, uvs, _)| uvs.iter().map(|uv| uv.x))
+                .fold((f32::MAX, f32::MIN), |(min, max), u| {
+                    (min.min(u), max.max(u))
+                });
+            // A triangle across a seam would reach from one end of the range to the other
+            for (_, uvs, triangles) in &surfaces {
+                for triangle in triangles.chunks_exact(3) {
+                    let us = triangle.iter().map(|&i| uvs[i as usize].x);
+                    let span = us.clone().fold(f32::MIN, f32::max) - us.fold(f32::MAX, f32::min);
+                    assert!(
+                        span <= (max - min) / 2.0,
+                        "{profile_kind:?} has a triangle spanning {span} of U"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn strips_draw_the_same_triangles_as_lists() {
+        let list = extrude(&road_config(), 12);
+        let strip = extrude(
+            &Config {
+                topology: Topology::TriangleStrip,
+                ..road_config()
+            },
+            12,
+        );
+        assert_eq!(list.len(), strip.len());
+        for ((list_positions, _, list_triangles), (strip_positions, _, strip_triangles)) in
+            list.iter().zip(&strip)
+        {
+            assert_eq!(list_positions, strip_positions);
+            assert!(!list_triangles.is_empty());
+            assert_eq!(
+                canonical_triangles(list_triangles),
+                canonical_triangles(strip_triangles)
+            );
+        }
+    }
+
+    #[test]
+    fn elevated_frames_stay_orthonormal() {
+        let config = Config {
+            elevation: vec![
+                ElevationKeyframe {
+                    position: 0.0,
+                    height: 0.0,
+                },
+                ElevationKeyframe {
+                    position: 1.0,
+                    height: 3.0,
+                },
+            ],
+            ..road_config()
+        };
+        // Already climbing, and framed about a tilted up as a transported frame can be, so the
+        // frame's X isn't level and the added grade isn't at right angles to it
+        let [a, b, c, d] = [
+            Vec3::ZERO,
+            Vec3::new(0.0, 1.0, 2.0),
+            Vec3::new(2.0, 2.0, 4.0),
+            Vec3::new(4.0, 2.0, 4.0),
+        ];
+        let tilted_up = Vec3::new(0.4, 1.0, 0.0).normalize();
+        let length = 6.0;
+        for i in 0..=8 {
+            let t = i as f32 / 8.0;
+            let frame = cubic_bezier_matrix(a, b, c, d, t, tilted_up);
+            let frame =
+                elevate_frame(frame, &config, t * length, length) * Mat4::from_rotation_z(0.3);
+            let axes = [frame.x_axis, frame.y_axis, frame.z_axis].map(|axis| axis.truncate());
+            for (i, axis) in axes.iter().enumerate() {
+                assert!((axis.length() - 1.0).abs() < 1e-4, "t = {t}, axis {i}");
+                for other in &axes[i + 1..] {
+                    assert!(axis.dot(*other).abs() < 1e-4, "t = {t}, axis {i}");
+                }
+            }
+            // Right handed, like the frame it started from
+            assert!(axes[0].cross(axes[1]).dot(axes[2]) > 0.0, "t = {t}");
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rayon"))]
+mod rayon_tests {
+    use super::*;
+    use bezier_mesh::curve::cubic_bezier_matrix;
+
+    #[test]
+    fn parallel_slices_match_sequential() {
+        let [a, b, c, d] = [
+            Vec3::ZERO,
+            Vec3::new(0.0, 1.0, 2.0),
+            Vec3::new(2.0, 1.0, 4.0),
+            Vec3::new(4.0, 0.0, 4.0),
+        ];
+        let count = 1000;
+        // Enough work per slice for the threads to finish out of order
+        let slice = |i: usize| {
+            let t = i as f32 / (count - 1) as f32;
+            let frame = cubic_bezier_matrix(a, b, c, d, t, Vec3::Y);
+            profile_vertices(&ProfileKind::Road, 2, t)
+                .into_iter()
+                .map(|vertex| (frame.transform_point3(vertex.point), vertex.uv))
+                .collect::<Vec<_>>()
+        };
+        let parallel = map_slices(count, &slice);
+        let sequential = (0..count).map(&slice).collect::<Vec<_>>();
+        assert_eq!(parallel, sequential);
     }
 }
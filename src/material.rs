@@ -5,9 +5,25 @@ use bevy::{
 };
 
 // This is the struct that will be passed to your shader
-#[derive(AsBindGroup, TypeUuid, Debug, Clone, Default)]
+#[derive(AsBindGroup, TypeUuid, Reflect, FromReflect, Debug, Clone)]
 #[uuid = "f690fdae-d598-45ab-8225-97e2a3f056e0"]
-pub struct UvDebugMaterial {}
+pub struct UvDebugMaterial {
+    /// Colour the UV gradient is multiplied by
+    #[uniform(0)]
+    pub tint: Color,
+    /// Number of grid cells across one unit of UV space
+    #[uniform(0)]
+    pub grid_scale: f32,
+}
+
+impl Default for UvDebugMaterial {
+    fn default() -> Self {
+        UvDebugMaterial {
+            tint: Color::WHITE,
+            grid_scale: 8.0,
+        }
+    }
+}
 
 /// The Material trait is very configurable, but comes with sensible defaults for all methods.
 /// You only need to implement functions for features that need non-default behavior. See the Material api docs for details!
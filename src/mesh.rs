@@ -0,0 +1,379 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Vertex attributes and indices of a generated mesh, kept apart from Bevy's `Mesh` so they can be
+/// post-processed before being uploaded
+#[derive(Default, Debug, Clone)]
+pub struct MeshData {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<Vec2>,
+    /// Linear RGBA, only present when the mesh is vertex coloured
+    pub colors: Option<Vec<[f32; 4]>>,
+    pub indices: Vec<u32>,
+    /// How `indices` make up triangles, either a list or a strip
+    pub topology: PrimitiveTopology,
+    /// Hand the mesh 16 bit indices instead of 32 bit ones when every vertex can be numbered with
+    /// them, halving the size of the index buffer. Bigger meshes keep 32 bit indices
+    pub prefer_u16: bool,
+}
+
+impl MeshData {
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Whether the mesh gets 16 bit indices, with `prefer_u16` and few enough vertices to number
+    pub fn uses_u16(&self) -> bool {
+        self.prefer_u16 && self.vertex_count() <= u16::MAX as usize
+    }
+
+    /// Empties every buffer but keeps its capacity, so the next mesh can be filled in without
+    /// reallocating
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.normals.clear();
+        self.uvs.clear();
+        if let Some(colors) = &mut self.colors {
+            colors.clear();
+        }
+        self.indices.clear();
+    }
+
+    /// Merges vertices whose position, normal, UV and colour all match to within `epsilon`, and
+    /// remaps the indices onto the merged vertices. Corners that need distinct normals or UVs keep
+    /// their separate vertices
+    pub fn weld(&mut self, epsilon: f32) {
+        let quantize = |value: f32| (value / epsilon).round() as i64;
+        let mut welded = MeshData {
+            colors: self.colors.as_ref().map(|_| vec![]),
+            topology: self.topology,
+            prefer_u16: self.prefer_u16,
+            ..default()
+        };
+        let mut lookup = HashMap::new();
+        let remap = (0..self.vertex_count())
+            .map(|i| {
+                let (position, normal, uv) = (self.positions[i], self.normals[i], self.uvs[i]);
+                let color = self.colors.as_ref().map_or([0.0; 4], |colors| colors[i]);
+                let key = [
+                    position.x, position.y, position.z, normal.x, normal.y, normal.z, uv.x, uv.y,
+                    color[0], color[1], color[2], color[3],
+                ]
+                .map(quantize);
+                *lookup.entry(key).or_insert_with(|| {
+                    welded.positions.push(position);
+                    welded.normals.push(normal);
+                    welded.uvs.push(uv);
+                    if let Some(colors) = &mut welded.colors {
+                        colors.push(color);
+                    }
+                    welded.positions.len() as u32 - 1
+                })
+            })
+            .collect::<Vec<_>>();
+        welded.indices = self.indices.iter().map(|&i| remap[i as usize]).collect();
+        *self = welded;
+    }
+
+    /// Turns the mesh inside out, reversing the winding of every triangle and pointing the normals
+    /// the other way
+    pub fn flip_faces(&mut self) {
+        match self.topology {
+            // Starting a strip one index later flips the winding of every triangle in it
+            PrimitiveTopology::TriangleStrip => {
+                if let Some(&first) = self.indices.first() {
+                    self.indices.insert(0, first);
+                }
+            }
+            _ => {
+                for triangle in self.indices.chunks_exact_mut(3) {
+                    triangle.swap(1, 2);
+                }
+            }
+        }
+        for normal in &mut self.normals {
+            *normal = -*normal;
+        }
+    }
+
+    /// Gives every triangle its own three vertices, all with the triangle's face normal, for a
+    /// faceted look. Strips are unrolled into a list, since neighbouring triangles can no longer
+    /// share vertices
+    pub fn flat_shade(&mut self) {
+        let triangles = match self.topology {
+            PrimitiveTopology::TriangleStrip => strip_to_list(&self.indices),
+            _ => self.indices.clone(),
+        };
+        let mut flat = MeshData {
+            colors: self.colors.as_ref().map(|_| vec![]),
+            topology: PrimitiveTopology::TriangleList,
+            prefer_u16: self.prefer_u16,
+            ..default()
+        };
+        for triangle in triangles.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize);
+            let [pa, pb, pc] = [a, b, c].map(|i| self.positions[i]);
+            let face_normal = (pb - pa).cross(pc - pa).normalize_or_zero();
+            for i in [a, b, c] {
+                flat.indices.push(flat.positions.len() as u32);
+                flat.positions.push(self.positions[i]);
+                flat.normals.push(face_normal);
+                flat.uvs.push(self.uvs[i]);
+                if let (Some(colors), Some(flat_colors)) = (&self.colors, &mut flat.colors) {
+                    flat_colors.push(colors[i]);
+                }
+            }
+        }
+        *self = flat;
+    }
+
+    /// Whether the triangles mostly wind the opposite way to their vertex normals, weighted by area.
+    /// Such a mesh renders its back faces, which look dark or see-through
+    pub fn looks_inverted(&self) -> bool {
+        let triangles = match self.topology {
+            PrimitiveTopology::TriangleStrip => strip_to_list(&self.indices),
+            _ => self.indices.clone(),
+        };
+        let agreement: f32 = triangles
+            .chunks_exact(3)
+            .map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize);
+                let [pa, pb, pc] = [a, b, c].map(|i| self.positions[i]);
+                let face_normal = (pb - pa).cross(pc - pa);
+                face_normal.dot(self.normals[a] + self.normals[b] + self.normals[c])
+            })
+            .sum();
+        agreement < 0.0
+    }
+
+    /// Copies the attributes and indices into `mesh`, replacing whatever it held. Buffers the mesh
+    /// already has are cleared and refilled in place, so rebuilding a mesh of a similar size doesn't
+    /// reallocate them. A mesh can't change topology in place, so it's replaced outright when the
+    /// topology differs
+    pub fn apply_to(&self, mesh: &mut Mesh) {
+        if mesh.primitive_topology() != self.topology {
+            *mesh = Mesh::new(self.topology);
+        }
+        let positions = self.positions.iter().map(|position| position.to_array());
+        match mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(buffer)) => refill(buffer, positions),
+            _ => mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions.collect::<Vec<_>>()),
+        }
+        let normals = self.normals.iter().map(|normal| normal.to_array());
+        match mesh.attribute_mut(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float32x3(buffer)) => refill(buffer, normals),
+            _ => mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals.collect::<Vec<_>>()),
+        }
+        let uvs = self.uvs.iter().map(|uv| uv.to_array());
+        match mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(buffer)) => refill(buffer, uvs),
+            _ => mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs.collect::<Vec<_>>()),
+        }
+        match (&self.colors, mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR)) {
+            (Some(colors), Some(VertexAttributeValues::Float32x4(buffer))) => {
+                refill(buffer, colors.iter().copied())
+            }
+            (Some(colors), _) => mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors.clone()),
+            (None, _) => {
+                mesh.remove_attribute(Mesh::ATTRIBUTE_COLOR);
+            }
+        }
+        match (self.uses_u16(), mesh.indices_mut()) {
+            (true, Some(Indices::U16(buffer))) => {
+                refill(buffer, self.indices.iter().map(|&i| i as u16))
+            }
+            (false, Some(Indices::U32(buffer))) => refill(buffer, self.indices.iter().copied()),
+            _ => mesh.set_indices(Some(self.mesh_indices())),
+        }
+    }
+
+    /// Indices in the format `uses_u16` picks
+    fn mesh_indices(&self) -> Indices {
+        if self.uses_u16() {
+            Indices::U16(self.indices.iter().map(|&i| i as u16).collect())
+        } else {
+            Indices::U32(self.indices.clone())
+        }
+    }
+
+    pub fn into_mesh(self) -> Mesh {
+        let indices = self.mesh_indices();
+        let mut mesh = Mesh::new(self.topology);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        if let Some(colors) = self.colors {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        }
+        mesh.set_indices(Some(indices));
+        mesh
+    }
+}
+
+/// Every triangle of `parts` in one indexed triangle list, with each part moved by its transform
+/// first, for writing several meshes out as a single object. Strips are unpacked into lists, and
+/// parts that aren't made of triangles are left out. Normals and UVs that a part doesn't have are
+/// filled with zeros, and colours with white when only some parts are coloured
+pub fn combine_meshes<'a>(parts: impl IntoIterator<Item = (&'a Mesh, Mat4)>) -> Mesh {
+    let mut combined = MeshData {
+        topology: PrimitiveTopology::TriangleList,
+        ..default()
+    };
+    for (mesh, transform) in parts {
+        let Some(triangles) = triangle_list(mesh) else {
+            continue;
+        };
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            continue;
+        };
+        let offset = combined.vertex_count() as u32;
+        let count = positions.len();
+        // Normals are carried by the inverse transpose, so they stay perpendicular under scaling
+        let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+        combined.positions.extend(
+            positions
+                .iter()
+                .map(|&position| transform.transform_point3(position.into())),
+        );
+        match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float32x3(normals)) => combined.normals.extend(
+                normals
+                    .iter()
+                    .map(|&normal| (normal_matrix * Vec3::from(normal)).normalize_or_zero()),
+            ),
+            _ => combined
+                .normals
+                .extend(std::iter::repeat(Vec3::ZERO).take(count)),
+        }
+        match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(uvs)) => {
+                combined.uvs.extend(uvs.iter().map(|&uv| Vec2::from(uv)))
+            }
+            _ => combined
+                .uvs
+                .extend(std::iter::repeat(Vec2::ZERO).take(count)),
+        }
+        match (mesh.attribute(Mesh::ATTRIBUTE_COLOR), &mut combined.colors) {
+            (Some(VertexAttributeValues::Float32x4(colors)), Some(combined_colors)) => {
+                combined_colors.extend(colors)
+            }
+            // The first coloured part colours every part before it white
+            (Some(VertexAttributeValues::Float32x4(colors)), None) => {
+                let mut combined_colors = vec![[1.0; 4]; offset as usize];
+                combined_colors.extend(colors);
+                combined.colors = Some(combined_colors);
+            }
+            (_, Some(combined_colors)) => {
+                combined_colors.extend(std::iter::repeat([1.0; 4]).take(count))
+            }
+            (_, None) => {}
+        }
+        combined
+            .indices
+            .extend(triangles.into_iter().map(|i| i + offset));
+    }
+    combined.into_mesh()
+}
+
+/// Indices of every triangle `mesh` draws, as a triangle list. Unindexed meshes are numbered in
+/// vertex order and strips are unpacked. `None` if the mesh isn't made of triangles
+pub fn triangle_list(mesh: &Mesh) -> Option<Vec<u32>> {
+    let indices = match mesh.indices() {
+        Some(indices) => indices.iter().map(|i| i as u32).collect(),
+        None => (0..mesh.count_vertices() as u32).collect(),
+    };
+    match mesh.primitive_topology() {
+        PrimitiveTopology::TriangleList => Some(indices),
+        PrimitiveTopology::TriangleStrip => Some(strip_to_list(&indices)),
+        _ => None,
+    }
+}
+
+/// Replaces the contents of `buffer` without giving up its allocation
+fn refill<T>(buffer: &mut Vec<T>, values: impl IntoIterator<Item = T>) {
+    buffer.clear();
+    buffer.extend(values);
+}
+
+/// Triangle list equivalent to a triangle strip, with the winding of every other triangle flipped
+/// back the way the strip draws it. Degenerate triangles used to join strips are left out
+pub fn strip_to_list(indices: &[u32]) -> Vec<u32> {
+    indices
+        .windows(3)
+        .enumerate()
+        .filter(|(_, triangle)| {
+            triangle[0] != triangle[1] && triangle[1] != triangle[2] && triangle[0] != triangle[2]
+        })
+        .flat_map(|(i, triangle)| {
+            if i % 2 == 0 {
+                [triangle[0], triangle[1], triangle[2]]
+            } else {
+                [triangle[1], triangle[0], triangle[2]]
+            }
+        })
+        .collect()
+}
+
+/// Number of triangles `mesh` draws. Degenerate triangles used to join strips aren't counted
+pub fn triangle_count(mesh: &Mesh) -> usize {
+    match (mesh.primitive_topology(), mesh.indices()) {
+        (PrimitiveTopology::TriangleStrip, Some(indices)) => indices
+            .iter()
+            .tuple_windows()
+            .filter(|(a, b, c)| a != b && b != c && a != c)
+            .count(),
+        (PrimitiveTopology::TriangleStrip, None) => mesh.count_vertices().saturating_sub(2),
+        (_, Some(indices)) => indices.len() / 3,
+        (_, None) => mesh.count_vertices() / 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A strip of `vertex_count` vertices along X, with a triangle reaching the last one
+    fn mesh_data(vertex_count: usize, prefer_u16: bool) -> MeshData {
+        let last = vertex_count as u32 - 1;
+        MeshData {
+            positions: (0..vertex_count).map(|i| Vec3::X * i as f32).collect(),
+            normals: vec![Vec3::Y; vertex_count],
+            uvs: vec![Vec2::ZERO; vertex_count],
+            indices: vec![0, last - 1, last],
+            topology: PrimitiveTopology::TriangleList,
+            prefer_u16,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn u16_indices_up_to_their_limit() {
+        for (vertex_count, prefer_u16, expect_u16) in [
+            (65535, true, true),
+            (65536, true, false),
+            (65535, false, false),
+        ] {
+            let data = mesh_data(vertex_count, prefer_u16);
+            assert_eq!(data.uses_u16(), expect_u16, "{vertex_count} vertices");
+
+            let mut applied = Mesh::new(PrimitiveTopology::TriangleList);
+            data.apply_to(&mut applied);
+            for mesh in [applied, data.clone().into_mesh()] {
+                match mesh.indices() {
+                    Some(Indices::U16(indices)) if expect_u16 => {
+                        assert_eq!(*indices, [0, 65533, 65534])
+                    }
+                    Some(Indices::U32(indices)) if !expect_u16 => {
+                        assert_eq!(*indices, data.indices)
+                    }
+                    indices => panic!("{vertex_count} vertices got {indices:?}"),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,562 @@
+use bevy::prelude::*;
+use itertools::Itertools;
+use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, TAU};
+use std::ops::Range;
+
+/// Height of the driving surface above the curve, in the profile's local space
+pub const ROAD_SURFACE_HEIGHT: f32 = 0.2;
+
+/// Part of the profile that's meshed and shaded separately from the rest
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SurfaceGroup {
+    /// Driving surface, which takes the road texture
+    Road,
+    /// Raised edges either side of the driving surface
+    Curb,
+    /// Outer walls and bottom
+    Underside,
+}
+
+/// How U is laid out around the profile
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, Default, PartialEq)]
+pub enum UvLayout {
+    /// Each profile's own layout. The road's takes one repeat of the texture per lane, so a road
+    /// texture with its markings lines up with the lanes, and the other faces take what's left
+    #[default]
+    Authored,
+    /// U runs once from 0 to 1 around the whole profile, with each face taking a share of the
+    /// range in proportion to its width, so every face samples a texture at the same density. See
+    /// [`atlas_ranges`] for where each face lands
+    Atlas,
+}
+
+/// Shape closing off each end of an open road
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, Default, PartialEq)]
+pub enum CapStyle {
+    /// Left open, showing the inside of the road
+    None,
+    /// Flat across the end of the profile
+    #[default]
+    Flat,
+    /// A dome reaching out from the end as far as the top of the profile, a hemisphere for a tube
+    Rounded,
+}
+
+/// Cross-section extruded along the curve
+#[derive(Reflect, FromReflect, Clone, Debug, Default)]
+pub enum ProfileKind {
+    /// Road surface with raised curbs either side
+    #[default]
+    Road,
+    /// Regular polygon, for pipes and cables
+    Tube { radius: f32, sides: usize },
+    /// Outline drawn point by point, in the XY plane with Y up and the curve at the origin, and
+    /// every point a hard corner. A `closed` outline joins back onto its first point and gets caps,
+    /// which fan out from the origin, so it should lie inside the outline or on its edge. An open
+    /// one, such as a channel, stops at its last point, and its faces look to the left of the
+    /// direction it's drawn in, so drawing it left to right faces it upwards
+    Custom { points: Vec<Vec2>, closed: bool },
+    /// Flat strip facing up, for rivers, trails and decals. Just the two edges, so it has no
+    /// thickness and no caps
+    Ribbon { width: f32 },
+    /// Face of a W-beam barrier standing `height` tall on the curve, with its two ridges facing
+    /// left. Open like the ribbon, so it's one sided and has no caps
+    Guardrail { height: f32 },
+}
+
+/// Point of a slice of the profile, with the normal and UV it's extruded with
+#[derive(Default, Clone, Debug)]
+pub struct Vertex {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub uv: Vec2,
+}
+
+impl Vertex {
+    pub fn new(point: Vec3, normal: Vec3, uv: Vec2) -> Self {
+        Vertex { point, normal, uv }
+    }
+}
+
+/// Height of the top of the profile above the curve, in the profile's local space
+pub fn profile_top(kind: &ProfileKind) -> f32 {
+    match *kind {
+        ProfileKind::Road => ROAD_SURFACE_HEIGHT,
+        ProfileKind::Tube { radius, .. } => radius,
+        ProfileKind::Ribbon { .. } => 0.0,
+        ProfileKind::Guardrail { height } => height,
+        ProfileKind::Custom { ref points, .. } => {
+            points.iter().map(|point| point.y).fold(0.0, f32::max)
+        }
+    }
+}
+
+/// Height of the bottom of the profile above the curve, in the profile's local space
+pub fn profile_bottom(kind: &ProfileKind) -> f32 {
+    match *kind {
+        ProfileKind::Road | ProfileKind::Ribbon { .. } | ProfileKind::Guardrail { .. } => 0.0,
+        ProfileKind::Tube { radius, .. } => -radius,
+        ProfileKind::Custom { ref points, .. } => {
+            points.iter().map(|point| point.y).fold(0.0, f32::min)
+        }
+    }
+}
+
+/// Vertices of one slice of the profile, relative to the point on the curve, with `v` as the V
+/// texture coordinate. The road surface is divided into `lanes` lanes of equal width
+pub fn profile_vertices(kind: &ProfileKind, lanes: usize, v: f32) -> Vec<Vertex> {
+    match *kind {
+        ProfileKind::Road => {
+            // U runs 0..lanes across the top, one repeat of the road texture per lane, then carries
+            // on around the outer walls and underside at one unit per unit of perimeter, ending
+            // back at the top left. Whole values of U land on the boundaries between lanes, with
+            // the curbs taking the texture's edges either side
+            let lanes = lanes.max(1);
+            let end = lanes as f32;
+            #[rustfmt::skip]
+            let mut road = vec![
+                // 0
+                Vertex::new(Vec3::new(-0.5, 0.3, 0.0), Vec3::NEG_X, Vec2::new(end + 1.6, v)),
+                Vertex::new(Vec3::new(-0.5, 0.3, 0.0), Vec3::Y, Vec2::new(0.0, v)),
+                // 1
+                Vertex::new(Vec3::new(-0.3, 0.3, 0.0), Vec3::Y, Vec2::new(0.05, v)),
+                Vertex::new(Vec3::new(-0.3, 0.3, 0.0), Vec3::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), Vec2::new(0.05, v)),
+                // 2
+                Vertex::new(Vec3::new(-0.2, 0.2, 0.0), Vec3::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), Vec2::new(0.1, v)),
+                Vertex::new(Vec3::new(-0.2, 0.2, 0.0), Vec3::Y, Vec2::new(0.1, v)),
+            ];
+            // Lane boundaries are corners too, so U can change pace across each lane. The lanes
+            // either side are level with each other, so they share a single vertex
+            road.extend((1..lanes).map(|lane| {
+                let x = -0.2 + 0.4 * lane as f32 / end;
+                Vertex::new(Vec3::new(x, 0.2, 0.0), Vec3::Y, Vec2::new(lane as f32, v))
+            }));
+            #[rustfmt::skip]
+            road.extend([
+                // 3
+                Vertex::new(Vec3::new(0.2, 0.2, 0.0), Vec3::Y, Vec2::new(end - 0.1, v)),
+                Vertex::new(Vec3::new(0.2, 0.2, 0.0), Vec3::new(-FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), Vec2::new(end - 0.1, v)),
+                // 4
+                Vertex::new(Vec3::new(0.3, 0.3, 0.0), Vec3::new(-FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), Vec2::new(end - 0.05, v)),
+                Vertex::new(Vec3::new(0.3, 0.3, 0.0), Vec3::Y, Vec2::new(end - 0.05, v)),
+                // 5
+                Vertex::new(Vec3::new(0.5, 0.3, 0.0), Vec3::Y, Vec2::new(end, v)),
+                Vertex::new(Vec3::new(0.5, 0.3, 0.0), Vec3::X, Vec2::new(end, v)),
+                // 6
+                Vertex::new(Vec3::new(0.5, 0.0, 0.0), Vec3::X, Vec2::new(end + 0.3, v)),
+                Vertex::new(Vec3::new(0.5, 0.0, 0.0), Vec3::NEG_Y, Vec2::new(end + 0.3, v)),
+                // 7
+                Vertex::new(Vec3::new(-0.5, 0.0, 0.0), Vec3::NEG_Y, Vec2::new(end + 1.3, v)),
+                Vertex::new(Vec3::new(-0.5, 0.0, 0.0), Vec3::NEG_X, Vec2::new(end + 1.3, v)),
+            ]);
+            road
+        }
+        ProfileKind::Tube { radius, sides } => tube_vertices(radius, sides, v),
+        ProfileKind::Custom { ref points, closed } => custom_vertices(points, closed, v),
+        ProfileKind::Guardrail { height } => custom_vertices(&guardrail_points(height), false, v),
+        // Left edge then right, with U running across the width
+        ProfileKind::Ribbon { width } => [(-0.5, 0.0), (0.5, 1.0)]
+            .into_iter()
+            .map(|(x, u)| Vertex::new(Vec3::new(x * width, 0.0, 0.0), Vec3::Y, Vec2::new(u, v)))
+            .collect(),
+    }
+}
+
+/// Whether the profile goes all the way round, with a face joining its last vertex back to its
+/// first. Only closed profiles enclose anything, so only they get caps at the ends of the road
+pub fn profile_is_closed(kind: &ProfileKind) -> bool {
+    match *kind {
+        ProfileKind::Road | ProfileKind::Tube { .. } => true,
+        ProfileKind::Custom { closed, .. } => closed,
+        ProfileKind::Ribbon { .. } | ProfileKind::Guardrail { .. } => false,
+    }
+}
+
+/// Points of a guardrail's face from the bottom up, bulging out to the left at each ridge
+fn guardrail_points(height: f32) -> [Vec2; 5] {
+    let ridge = -0.2 * height;
+    [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(ridge, 0.25 * height),
+        Vec2::new(0.0, 0.5 * height),
+        Vec2::new(ridge, 0.75 * height),
+        Vec2::new(0.0, height),
+    ]
+}
+
+/// `points` running clockwise (looking along the curve) like the built in profiles, so the faces
+/// of a `closed` outline wind outwards whichever way it was drawn. An open outline is kept the way
+/// it was drawn. Empty when there are too few points to make a face, or to enclose anything when
+/// `closed`
+fn custom_outline(points: &[Vec2], closed: bool) -> Vec<Vec2> {
+    if !closed {
+        return if points.len() < 2 {
+            vec![]
+        } else {
+            points.to_vec()
+        };
+    }
+    if points.len() < 3 {
+        return vec![];
+    }
+    // Shoelace formula, positive for an anticlockwise outline
+    let area = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(p, q)| p.perp_dot(*q))
+        .sum::<f32>();
+    if area > 0.0 {
+        points.iter().rev().copied().collect()
+    } else {
+        points.to_vec()
+    }
+}
+
+/// Vertices of one slice of a custom outline. Each corner is split in two like the road's: first
+/// with the normal of the face coming into it, then with the normal of the face leaving it. U runs
+/// one unit per unit of perimeter, with the face closing the outline ending at the full perimeter.
+/// The ends of an open outline have a face on one side only, so both halves take its normal
+fn custom_vertices(points: &[Vec2], closed: bool, v: f32) -> Vec<Vertex> {
+    let outline = custom_outline(points, closed);
+    let n = outline.len();
+    if n == 0 {
+        return vec![];
+    }
+    // Clockwise, so the outward normal is the edge direction turned anticlockwise
+    let normal = |i: usize| {
+        let edge = outline[(i + 1) % n] - outline[i];
+        edge.perp().normalize_or_zero().extend(0.0)
+    };
+    // Perimeter up to each corner, and all the way round
+    let mut distances = vec![0.0; n + 1];
+    for i in 0..n {
+        distances[i + 1] = distances[i] + outline[i].distance(outline[(i + 1) % n]);
+    }
+    let perimeter = distances[n];
+    (0..n)
+        .flat_map(|i| {
+            let point = outline[i].extend(0.0);
+            let incoming_u = if i == 0 { perimeter } else { distances[i] };
+            let (incoming, outgoing) = match i {
+                0 if !closed => (0, 0),
+                _ if i == n - 1 && !closed => (n - 2, n - 2),
+                _ => ((i + n - 1) % n, i),
+            };
+            [
+                Vertex::new(point, normal(incoming), Vec2::new(incoming_u, v)),
+                Vertex::new(point, normal(outgoing), Vec2::new(distances[i], v)),
+            ]
+        })
+        .collect()
+}
+
+/// Pairs of profile vertices joined by faces along the curve, in the order that winds the faces
+/// outwards
+pub fn profile_edges(kind: &ProfileKind, lanes: usize) -> Vec<[u32; 2]> {
+    match *kind {
+        // Each of the eight corners around the outside of the road is split into two vertices
+        // with different normals, so the faces run from the second vertex of one corner to the
+        // first vertex of the next. The corners between lanes have just the one vertex, which both
+        // lanes either side use
+        ProfileKind::Road => {
+            let boundaries = lanes.max(1) as u32 - 1;
+            // First and second vertex of each corner in turn
+            let corners = [(0, 1), (2, 3), (4, 5)]
+                .into_iter()
+                .chain((6..6 + boundaries).map(|i| (i, i)))
+                .chain((0..5).map(|j| (6 + boundaries + 2 * j, 7 + boundaries + 2 * j)))
+                .collect::<Vec<_>>();
+            corner_edges(&corners, true)
+        }
+        // The last face runs to the copy of the first vertex that closes the seam
+        ProfileKind::Tube { sides, .. } => {
+            let sides = sides.max(MIN_TUBE_SIDES) as u32;
+            (0..sides).map(|i| [i, i + 1]).collect()
+        }
+        // Corners are split the same way as the road's
+        ProfileKind::Custom { ref points, closed } => {
+            let corners = (0..custom_outline(points, closed).len() as u32)
+                .map(|i| (2 * i, 2 * i + 1))
+                .collect::<Vec<_>>();
+            corner_edges(&corners, closed)
+        }
+        ProfileKind::Guardrail { height } => {
+            let corners = (0..guardrail_points(height).len() as u32)
+                .map(|i| (2 * i, 2 * i + 1))
+                .collect::<Vec<_>>();
+            corner_edges(&corners, false)
+        }
+        // Left to right winds the one face upwards, like the lanes
+        ProfileKind::Ribbon { .. } => vec![[0, 1]],
+    }
+}
+
+/// Faces running from the second vertex of each of `corners` to the first vertex of the next, and
+/// from the last corner back round to the first when `closed`
+fn corner_edges(corners: &[(u32, u32)], closed: bool) -> Vec<[u32; 2]> {
+    let faces = if closed {
+        corners.len()
+    } else {
+        corners.len().saturating_sub(1)
+    };
+    (0..faces)
+        .map(|i| [corners[i].1, corners[(i + 1) % corners.len()].0])
+        .collect()
+}
+
+/// The profile's edges split into the surfaces they belong to. The road's curbs are the faces
+/// either side of the lanes, and everything below them is the underside. Other profiles are all road
+pub fn profile_surfaces(kind: &ProfileKind, lanes: usize) -> Vec<(SurfaceGroup, Vec<[u32; 2]>)> {
+    let edges = profile_edges(kind, lanes);
+    match *kind {
+        ProfileKind::Road => {
+            // Two curb faces (top and bevel) on the left, the lanes, then the same on the right
+            let lanes = lanes.max(1);
+            let group = |face: usize| match face {
+                _ if face < 2 => SurfaceGroup::Curb,
+                _ if face < 2 + lanes => SurfaceGroup::Road,
+                _ if face < 4 + lanes => SurfaceGroup::Curb,
+                _ => SurfaceGroup::Underside,
+            };
+            [
+                SurfaceGroup::Road,
+                SurfaceGroup::Curb,
+                SurfaceGroup::Underside,
+            ]
+            .into_iter()
+            .map(|surface| {
+                let surface_edges = (0..edges.len())
+                    .filter(|&face| group(face) == surface)
+                    .map(|face| edges[face])
+                    .collect();
+                (surface, surface_edges)
+            })
+            .collect()
+        }
+        ProfileKind::Tube { .. }
+        | ProfileKind::Custom { .. }
+        | ProfileKind::Ribbon { .. }
+        | ProfileKind::Guardrail { .. } => vec![(SurfaceGroup::Road, edges)],
+    }
+}
+
+/// U range of each face of the profile, in the same order as [`profile_edges`], when it's laid out
+/// as an atlas. The faces share 0..1 in proportion to their authored widths, working round the
+/// profile in order, so a texture artist can paint each face's part of the texture at a matching
+/// density
+pub fn atlas_ranges(kind: &ProfileKind, lanes: usize) -> Vec<Range<f32>> {
+    let vertices = profile_vertices(kind, lanes, 0.0);
+    let widths = profile_edges(kind, lanes)
+        .into_iter()
+        .map(|[a, b]| {
+            vertices[a as usize]
+                .point
+                .distance(vertices[b as usize].point)
+        })
+        .collect::<Vec<_>>();
+    let perimeter = widths.iter().sum::<f32>().max(f32::EPSILON);
+    widths
+        .iter()
+        .scan(0.0, |start, width| {
+            let range = *start / perimeter..(*start + width) / perimeter;
+            *start += width;
+            Some(range)
+        })
+        .collect()
+}
+
+/// Replaces the U of `vertices`, one slice of the profile, with the atlas layout of
+/// [`atlas_ranges`]. Vertices shared by neighbouring faces, such as round a tube, land on the
+/// boundary between their ranges either way
+pub fn apply_atlas(kind: &ProfileKind, lanes: usize, vertices: &mut [Vertex]) {
+    for ([a, b], range) in profile_edges(kind, lanes)
+        .into_iter()
+        .zip(atlas_ranges(kind, lanes))
+    {
+        vertices[a as usize].uv.x = range.start;
+        vertices[b as usize].uv.x = range.end;
+    }
+}
+
+/// Profile vertices used by `edges`, in order, and the edges renumbered to index into them
+pub fn surface_vertices(edges: &[[u32; 2]]) -> (Vec<u32>, Vec<[u32; 2]>) {
+    let used = edges
+        .iter()
+        .flatten()
+        .copied()
+        .sorted()
+        .dedup()
+        .collect::<Vec<_>>();
+    let renumber = |i: u32| used.binary_search(&i).unwrap() as u32;
+    let edges = edges
+        .iter()
+        .map(|&[a, b]| [renumber(a), renumber(b)])
+        .collect();
+    (used, edges)
+}
+
+/// Triangles joining one slice to the next, as indices into the first slice's vertices. Slices are
+/// `stride` vertices apart
+pub fn slice_triangles(edges: &[[u32; 2]], stride: usize) -> Vec<u32> {
+    let next = stride as u32;
+    edges
+        .iter()
+        .flat_map(|&[a, b]| [a, b + next, a + next, a, b, b + next])
+        .collect()
+}
+
+/// Rings of vertices in a rounded cap, besides the end slice it starts from
+const CAP_RINGS: usize = 8;
+
+/// Rings of vertices that `cap_rings` adds at each end
+pub fn cap_ring_count(style: CapStyle) -> usize {
+    match style {
+        CapStyle::None => 0,
+        CapStyle::Flat => 2,
+        CapStyle::Rounded => CAP_RINGS,
+    }
+}
+
+/// Rings of vertices closing off the end of the road at the slice with `end_vertices`, in the
+/// profile's local space, starting next to the slice and finishing at the profile's origin, which
+/// every profile can be fanned out from. `outward` points away from the road along the curve, and
+/// `depth` is how far a rounded cap reaches along it. V carries on from the slice at
+/// `texture_tiling` per unit along the cap's surface
+pub fn cap_rings(
+    end_vertices: &[Vertex],
+    outward: Vec3,
+    style: CapStyle,
+    depth: f32,
+    texture_tiling: f32,
+) -> Vec<Vec<Vertex>> {
+    match style {
+        CapStyle::None => vec![],
+        // The end slice again facing outwards, so the face gets hard edges, then the origin
+        CapStyle::Flat => vec![
+            end_vertices
+                .iter()
+                .map(|vertex| Vertex::new(vertex.point, outward, vertex.uv))
+                .collect(),
+            end_vertices
+                .iter()
+                .map(|vertex| {
+                    let v = vertex.uv.y + vertex.point.length() * texture_tiling;
+                    Vertex::new(Vec3::ZERO, outward, Vec2::new(vertex.uv.x, v))
+                })
+                .collect(),
+        ],
+        // The profile shrinks towards its origin as it reaches out, with each vertex's normal
+        // turning from the profile's towards `outward`. For a tube that's exactly a hemisphere
+        CapStyle::Rounded => (1..=CAP_RINGS)
+            .map(|ring| {
+                let angle = ring as f32 / CAP_RINGS as f32 * FRAC_PI_2;
+                let (sin, cos) = angle.sin_cos();
+                end_vertices
+                    .iter()
+                    .map(|vertex| {
+                        Vertex::new(
+                            vertex.point * cos + outward * depth * sin,
+                            (vertex.normal * cos + outward * sin).normalize(),
+                            Vec2::new(vertex.uv.x, vertex.uv.y + depth * angle * texture_tiling),
+                        )
+                    })
+                    .collect()
+            })
+            .collect(),
+    }
+}
+
+/// Fewest sides a tube can have and still enclose anything
+const MIN_TUBE_SIDES: usize = 3;
+
+/// Vertices of one slice of a tube, a regular polygon with radial normals. Vertices run clockwise
+/// (looking along the curve) starting from the right, matching the winding of the road profile. U
+/// follows the angle around the tube. The first vertex is repeated at the end with a U of 1, so the
+/// face closing the tube doesn't stretch the whole texture back across itself at the seam
+fn tube_vertices(radius: f32, sides: usize, v: f32) -> Vec<Vertex> {
+    let sides = sides.max(MIN_TUBE_SIDES);
+    (0..=sides)
+        .map(|i| {
+            let fraction = i as f32 / sides as f32;
+            let normal = Vec3::new((-fraction * TAU).cos(), (-fraction * TAU).sin(), 0.0);
+            Vertex::new(normal * radius, normal, Vec2::new(fraction, v))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn closed_profiles() -> Vec<(ProfileKind, usize)> {
+        vec![
+            (ProfileKind::Road, 1),
+            (ProfileKind::Road, 3),
+            (
+                ProfileKind::Tube {
+                    radius: 0.5,
+                    sides: 8,
+                },
+                1,
+            ),
+            (
+                ProfileKind::Custom {
+                    points: vec![
+                        Vec2::new(-0.5, 0.0),
+                        Vec2::new(-0.5, 0.5),
+                        Vec2::new(0.5, 0.5),
+                        Vec2::new(0.5, 0.0),
+                    ],
+                    closed: true,
+                },
+                1,
+            ),
+        ]
+    }
+
+    #[test]
+    fn one_lane_road_matches_the_original_triangles() {
+        // The hand written template the road was first built with, one slice of 16 vertices to the
+        // next. It started with a triangle (0, 16, 31) joining two copies of the same corner, which
+        // had no area and isn't generated any more
+        #[rustfmt::skip]
+        let base_tris: Vec<u32> = vec![
+            1, 18,17,
+            1, 2, 18,
+            3, 20,19,
+            3, 4, 20,
+            5, 22,21,
+            5, 6, 22,
+            7, 24,23,
+            7, 8, 24,
+            9, 26,25,
+            9, 10,26,
+            11,28,27,
+            11,12,28,
+            13,30,29,
+            13,14,30,
+            15,16,31,
+            15,0, 16,
+        ];
+        let stride = profile_vertices(&ProfileKind::Road, 1, 0.0).len();
+        assert_eq!(stride, 16);
+        let edges = profile_edges(&ProfileKind::Road, 1);
+        assert_eq!(slice_triangles(&edges, stride), base_tris);
+    }
+
+    #[test]
+    fn u_increases_around_the_loop() {
+        for (kind, lanes) in closed_profiles() {
+            let vertices = profile_vertices(&kind, lanes, 0.0);
+            let u = |i: u32| vertices[i as usize].uv.x;
+            let edges = profile_edges(&kind, lanes);
+            for &[a, b] in &edges {
+                assert!(u(b) > u(a), "{kind:?} face {a}-{b} runs backwards in U");
+            }
+            // Each face carries on from where the last one finished
+            for faces in edges.windows(2) {
+                assert_eq!(u(faces[0][1]), u(faces[1][0]), "{kind:?} jumps in U");
+            }
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use crate::curve::{
+    closed_chain, curve_frames, is_cubic_chain, polyline_frames, segments, FrameMode,
+};
+use crate::mesh::MeshData;
+use crate::profile::{
+    cap_rings, profile_is_closed, profile_surfaces, profile_top, profile_vertices, slice_triangles,
+    surface_vertices, CapStyle, ProfileKind, Vertex,
+};
+use bevy::prelude::*;
+use bevy::render::mesh::PrimitiveTopology;
+
+/// Extrudes a profile along a chain of cubic Bézier segments into a single mesh, with every
+/// surface of the profile in one triangle list. Slices are spaced evenly in `t` along each segment,
+/// and V runs one unit per unit of length along the curve
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bezier_mesh::road::RoadBuilder;
+/// let points = [Vec3::ZERO, Vec3::X, Vec3::new(2.0, 0.0, 1.0), Vec3::new(3.0, 0.0, 1.0)];
+/// let mesh = RoadBuilder::new().detail(10).build(&points);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RoadBuilder {
+    profile: ProfileKind,
+    lanes: usize,
+    detail: usize,
+    frame_mode: FrameMode,
+    closed: bool,
+    end_caps: CapStyle,
+}
+
+impl Default for RoadBuilder {
+    fn default() -> Self {
+        RoadBuilder {
+            profile: ProfileKind::Road,
+            lanes: 1,
+            detail: 20,
+            frame_mode: FrameMode::WorldUp,
+            closed: false,
+            end_caps: CapStyle::Flat,
+        }
+    }
+}
+
+impl RoadBuilder {
+    /// A one lane road with curbs, 20 slices to a segment, kept upright against `Vec3::Y`, open
+    /// and with flat caps
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cross-section to extrude, the road with curbs by default
+    ///
+    /// ```
+    /// # use bezier_mesh::profile::ProfileKind;
+    /// # use bezier_mesh::road::RoadBuilder;
+    /// let pipe = RoadBuilder::new().profile(ProfileKind::Tube { radius: 0.1, sides: 12 });
+    /// ```
+    pub fn profile(mut self, profile: ProfileKind) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Lanes the road profile's driving surface is divided into. Other profiles ignore it
+    ///
+    /// ```
+    /// # use bezier_mesh::road::RoadBuilder;
+    /// let dual_carriageway = RoadBuilder::new().lanes(2);
+    /// ```
+    pub fn lanes(mut self, lanes: usize) -> Self {
+        self.lanes = lanes;
+        self
+    }
+
+    /// Slices to each segment, counting both ends. At least 2
+    ///
+    /// ```
+    /// # use bezier_mesh::road::RoadBuilder;
+    /// let coarse = RoadBuilder::new().detail(4);
+    /// ```
+    pub fn detail(mut self, detail: usize) -> Self {
+        self.detail = detail;
+        self
+    }
+
+    /// How each slice is turned about the curve. `FrameMode::WorldUp` and
+    /// `FrameMode::ParallelTransport` start from `Vec3::Y`
+    ///
+    /// ```
+    /// # use bezier_mesh::curve::FrameMode;
+    /// # use bezier_mesh::road::RoadBuilder;
+    /// let loop_the_loop = RoadBuilder::new().frame_mode(FrameMode::ParallelTransport);
+    /// ```
+    pub fn frame_mode(mut self, frame_mode: FrameMode) -> Self {
+        self.frame_mode = frame_mode;
+        self
+    }
+
+    /// Join the last control point back to the first with one more segment, mirroring the handles
+    /// either side so the loop stays smooth. Closed roads have no caps
+    ///
+    /// ```
+    /// # use bezier_mesh::road::RoadBuilder;
+    /// let circuit = RoadBuilder::new().closed(true);
+    /// ```
+    pub fn closed(mut self, closed: bool) -> Self {
+        self.closed = closed;
+        self
+    }
+
+    /// Shape closing off each end of an open road. Profiles that don't enclose anything, like the
+    /// ribbon, are left open
+    ///
+    /// ```
+    /// # use bezier_mesh::profile::CapStyle;
+    /// # use bezier_mesh::road::RoadBuilder;
+    /// let rounded = RoadBuilder::new().end_caps(CapStyle::Rounded);
+    /// ```
+    pub fn end_caps(mut self, end_caps: CapStyle) -> Self {
+        self.end_caps = end_caps;
+        self
+    }
+
+    /// Mesh of the profile extruded along `control_points`, a chain of `3n + 1` points making `n`
+    /// cubic segments. Empty when the points don't make whole segments
+    pub fn build(&self, control_points: &[Vec3]) -> Mesh {
+        if !is_cubic_chain(control_points) {
+            return MeshData::default().into_mesh();
+        }
+        let chain = if self.closed {
+            closed_chain(control_points)
+        } else {
+            control_points.to_vec()
+        };
+        // Segments share their end slices, so every segment after the first skips its first
+        let detail = self.detail.max(2);
+        let slices = segments(&chain)
+            .enumerate()
+            .flat_map(|(i, segment)| {
+                let first_slice = if i == 0 { 0 } else { 1 };
+                (first_slice..detail).map(move |j| (segment, j as f32 / (detail - 1) as f32))
+            })
+            .collect::<Vec<_>>();
+        self.extrude(&curve_frames(&slices, self.frame_mode, Vec3::Y))
+    }
+
+    /// Mesh of the profile extruded along `points`, a centerline such as a path found between
+    /// waypoints, followed exactly with straight lines between the points and no curve fitted
+    /// through them. Each line is sliced `detail` times and the frames are carried from point to
+    /// point by `frame_mode`, turning at a mitre where the line turns, which pinches the road a
+    /// little through sharp turns. Empty with fewer than two points
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bezier_mesh::road::RoadBuilder;
+    /// let waypoints = [Vec3::ZERO, Vec3::X, Vec3::new(1.0, 0.0, 1.0)];
+    /// let mesh = RoadBuilder::new().detail(2).build_polyline(&waypoints);
+    /// ```
+    pub fn build_polyline(&self, points: &[Vec3]) -> Mesh {
+        if points.len() < 2 {
+            return MeshData::default().into_mesh();
+        }
+        let mut line = points.to_vec();
+        if self.closed {
+            line.push(points[0]);
+        }
+        // Waypoints are shared between lines, so every line after the first skips its first slice
+        let detail = self.detail.max(2);
+        let slices = line
+            .windows(2)
+            .enumerate()
+            .flat_map(|(i, pair)| {
+                let first_slice = if i == 0 { 0 } else { 1 };
+                (first_slice..detail)
+                    .map(move |j| pair[0].lerp(pair[1], j as f32 / (detail - 1) as f32))
+            })
+            .collect::<Vec<_>>();
+        let frames = slices
+            .iter()
+            .copied()
+            .zip(polyline_frames(&slices, self.frame_mode, Vec3::Y))
+            .collect::<Vec<_>>();
+        self.extrude(&frames)
+    }
+
+    /// Mesh of the profile placed at each of `frames`, a point on the centerline and the rotation of
+    /// the slice there, with every surface in one triangle list
+    fn extrude(&self, frames: &[(Vec3, Quat)]) -> Mesh {
+        let mut data = MeshData {
+            topology: PrimitiveTopology::TriangleList,
+            ..default()
+        };
+
+        // Local vertices of each slice, with V following the distance along the curve
+        let mut distance = 0.0;
+        let mut rings = frames
+            .iter()
+            .enumerate()
+            .map(|(i, &(position, _))| {
+                if i > 0 {
+                    distance += position.distance(frames[i - 1].0);
+                }
+                profile_vertices(&self.profile, self.lanes, distance)
+            })
+            .collect::<Vec<_>>();
+        let place = |(position, rotation): (Vec3, Quat), ring: Vec<Vertex>| {
+            ring.into_iter()
+                .map(|vertex| {
+                    Vertex::new(
+                        position + rotation * vertex.point,
+                        rotation * vertex.normal,
+                        vertex.uv,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        // Caps are built in the profile's local space, so they're placed along with the slices
+        let (mut start_cap, mut end_cap) = (vec![], vec![]);
+        if !self.closed && profile_is_closed(&self.profile) {
+            let depth = profile_top(&self.profile);
+            start_cap = cap_rings(&rings[0], Vec3::Z, self.end_caps, depth, -1.0);
+            start_cap.reverse();
+            end_cap = cap_rings(
+                &rings[rings.len() - 1],
+                Vec3::NEG_Z,
+                self.end_caps,
+                depth,
+                1.0,
+            );
+        }
+        let (first_frame, last_frame) = (frames[0], frames[frames.len() - 1]);
+        rings = start_cap
+            .into_iter()
+            .map(|ring| place(first_frame, ring))
+            .chain(
+                rings
+                    .into_iter()
+                    .zip(frames)
+                    .map(|(ring, &frame)| place(frame, ring)),
+            )
+            .chain(end_cap.into_iter().map(|ring| place(last_frame, ring)))
+            .collect();
+
+        for (_, edges) in profile_surfaces(&self.profile, self.lanes) {
+            // Each surface only keeps the profile vertices its own faces use
+            let (used, edges) = surface_vertices(&edges);
+            let first = data.vertex_count() as u32;
+            let stride = used.len();
+            for ring in &rings {
+                for &i in &used {
+                    let vertex = &ring[i as usize];
+                    data.positions.push(vertex.point);
+                    data.normals.push(vertex.normal);
+                    data.uvs.push(vertex.uv);
+                }
+            }
+            let triangles = slice_triangles(&edges, stride);
+            for ring in 0..rings.len().saturating_sub(1) {
+                let ring_start = first + (ring * stride) as u32;
+                data.indices
+                    .extend(triangles.iter().map(|&i| i + ring_start));
+            }
+        }
+        data.into_mesh()
+    }
+}
+
+/// Mesh of the default road profile extruded along `points`, followed exactly and sliced
+/// `detail_per_segment` times between each pair of points, with parallel transport frames. See
+/// [`RoadBuilder::build_polyline`] for more control
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bezier_mesh::road::build_road_from_polyline;
+/// let path = [Vec3::ZERO, Vec3::X, Vec3::new(2.0, 0.0, 1.0)];
+/// let mesh = build_road_from_polyline(&path, 4);
+/// ```
+pub fn build_road_from_polyline(points: &[Vec3], detail_per_segment: usize) -> Mesh {
+    RoadBuilder::new()
+        .detail(detail_per_segment)
+        .frame_mode(FrameMode::ParallelTransport)
+        .build_polyline(points)
+}
@@ -0,0 +1,87 @@
+use bevy::reflect::serde::{ReflectSerializer, UntypedReflectDeserializer};
+use bevy::reflect::{Reflect, TypeRegistryInternal};
+use serde::de::DeserializeSeed;
+use std::error::Error;
+use std::fmt;
+
+/// Version of the format written by [`encode_design`]. Bumped whenever a change would stop older
+/// strings from decoding to the same design
+pub const DESIGN_VERSION: u32 = 1;
+
+/// Start of every design string, followed by the version
+const DESIGN_TAG: &str = "bezier_mesh-v";
+
+/// Why a string couldn't be decoded back into a design
+#[derive(Debug, Clone, PartialEq)]
+pub enum DesignError {
+    /// The string doesn't start with the design tag, so isn't a design at all
+    NotADesign,
+    /// A design written in a different version of the format
+    UnsupportedVersion(String),
+    /// The tag and version are right but the rest can't be read, or a value doesn't match the type
+    /// it names
+    Malformed(String),
+}
+
+impl fmt::Display for DesignError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DesignError::NotADesign => write!(f, "not a road design"),
+            DesignError::UnsupportedVersion(version) => write!(
+                f,
+                "road design is version {version}, but only version {DESIGN_VERSION} can be read"
+            ),
+            DesignError::Malformed(reason) => write!(f, "malformed road design: {reason}"),
+        }
+    }
+}
+
+impl Error for DesignError {}
+
+/// Encodes `values` as a single line that can be pasted around, for [`decode_design`] to read
+/// back. Each value is written as RON through reflection, so its type has to be in `registry`, and
+/// then base64 encoded. The line starts with a version tag, followed by the values separated by
+/// dots
+pub fn encode_design(
+    values: &[&dyn Reflect],
+    registry: &TypeRegistryInternal,
+) -> Result<String, DesignError> {
+    let mut design = format!("{DESIGN_TAG}{DESIGN_VERSION}");
+    for value in values {
+        let ron = ron::to_string(&ReflectSerializer::new(*value, registry))
+            .map_err(|err| DesignError::Malformed(err.to_string()))?;
+        design.push('.');
+        design.push_str(&base64::encode_config(ron, base64::URL_SAFE_NO_PAD));
+    }
+    Ok(design)
+}
+
+/// Values written by [`encode_design`], in the same order. They come back as dynamic values, to be
+/// applied onto values of the types they were written from. Surrounding whitespace is ignored,
+/// since pasted text often picks some up
+pub fn decode_design(
+    design: &str,
+    registry: &TypeRegistryInternal,
+) -> Result<Vec<Box<dyn Reflect>>, DesignError> {
+    let mut parts = design.trim().split('.');
+    let version = parts
+        .next()
+        .and_then(|tag| tag.strip_prefix(DESIGN_TAG))
+        .ok_or(DesignError::NotADesign)?;
+    if version != DESIGN_VERSION.to_string() {
+        return Err(DesignError::UnsupportedVersion(version.into()));
+    }
+    parts
+        .map(|part| {
+            let bytes = base64::decode_config(part, base64::URL_SAFE_NO_PAD)
+                .map_err(|err| DesignError::Malformed(err.to_string()))?;
+            let ron =
+                String::from_utf8(bytes).map_err(|err| DesignError::Malformed(err.to_string()))?;
+            let mut deserializer = ron::Deserializer::from_str(&ron)
+                .map_err(|err| DesignError::Malformed(err.to_string()))?;
+            UntypedReflectDeserializer::new(registry)
+                .deserialize(&mut deserializer)
+                .map_err(|err| DesignError::Malformed(err.to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+use std::error::Error;
+use std::fmt;
+
+/// Why an SVG path couldn't be turned into a Bézier chain
+#[derive(Debug, Clone, PartialEq)]
+pub enum SvgPathError {
+    /// The document has no `<path>` with a `d` attribute
+    NoPath,
+    /// A command that can't be represented as cubic segments, such as an arc
+    UnsupportedCommand(char),
+    /// A second `M`, which would start a separate curve
+    MultipleSubpaths,
+    /// Numbers that don't belong to any command, or a command missing some of its numbers
+    MalformedData,
+}
+
+impl fmt::Display for SvgPathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SvgPathError::NoPath => write!(f, "no <path> with a d attribute"),
+            SvgPathError::UnsupportedCommand(command) => {
+                write!(f, "unsupported path command '{command}'")
+            }
+            SvgPathError::MultipleSubpaths => write!(f, "only a single subpath can be imported"),
+            SvgPathError::MalformedData => write!(f, "malformed path data"),
+        }
+    }
+}
+
+impl Error for SvgPathError {}
+
+/// Bézier chain traced by the first `<path>` in an SVG document. See [`parse_path_data`]
+pub fn import_svg(svg: &str) -> Result<Vec<Vec2>, SvgPathError> {
+    parse_path_data(path_data(svg).ok_or(SvgPathError::NoPath)?)
+}
+
+/// Value of the `d` attribute of the first `<path>` element
+fn path_data(svg: &str) -> Option<&str> {
+    let start = svg.find("<path")? + "<path".len();
+    let tag = &svg[start..start + svg[start..].find('>')?];
+    // Match on the whitespace before it too, so `id="..."` isn't taken for `d="..."`
+    let (value_start, quote) = ["d=\"", "d='"].into_iter().find_map(|pattern| {
+        tag.match_indices(pattern)
+            .find(|(i, _)| tag[..*i].ends_with(char::is_whitespace))
+            .map(|(i, _)| (i + pattern.len(), pattern.chars().last().unwrap()))
+    })?;
+    let value = &tag[value_start..];
+    value.find(quote).map(|end| &value[..end])
+}
+
+enum Token {
+    Command(char),
+    Number(f32),
+}
+
+/// Splits path data into commands and numbers. Numbers can run straight into each other where a
+/// sign or a second decimal point makes the boundary clear, as in `1-2` or `.5.5`
+fn tokenize(d: &str) -> Result<Vec<Token>, SvgPathError> {
+    let mut tokens = vec![];
+    let mut chars = d.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() || c == ',' {
+            chars.next();
+        } else if c.is_ascii_alphabetic() {
+            tokens.push(Token::Command(c));
+            chars.next();
+        } else {
+            let mut end = start;
+            let (mut seen_point, mut seen_exponent) = (false, false);
+            while let Some(&(i, c)) = chars.peek() {
+                let previous = d[..i].chars().last();
+                let accepted = match c {
+                    '0'..='9' => true,
+                    '+' | '-' => i == start || matches!(previous, Some('e' | 'E')),
+                    '.' if !seen_point && !seen_exponent => {
+                        seen_point = true;
+                        true
+                    }
+                    'e' | 'E' if !seen_exponent && i > start => {
+                        seen_exponent = true;
+                        true
+                    }
+                    _ => false,
+                };
+                if !accepted {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let number = d[start..end]
+                .parse()
+                .map_err(|_| SvgPathError::MalformedData)?;
+            tokens.push(Token::Number(number));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Bézier chain traced by SVG path data, as an anchor followed by `[handle, handle, anchor]` for
+/// each segment. Cubic commands (`C`/`c`) carry over as they are, sharing their end points. Straight
+/// lines (`L`, `H`, `V` and `Z`, either case) become straight cubics with a warning. Anything else
+/// is rejected, as is a second subpath
+pub fn parse_path_data(d: &str) -> Result<Vec<Vec2>, SvgPathError> {
+    let tokens = tokenize(d)?;
+    let mut points: Vec<Vec2> = vec![];
+    let (mut current, mut subpath_start) = (Vec2::ZERO, Vec2::ZERO);
+    let mut command = None;
+    let mut linearized = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Token::Command(c) = tokens[i] {
+            command = Some(c);
+            i += 1;
+            // Closing takes no numbers, so it can't be repeated implicitly like the others
+            if c == 'Z' || c == 'z' {
+                if current != subpath_start {
+                    points.extend(straight_cubic(current, subpath_start));
+                    linearized = true;
+                }
+                current = subpath_start;
+                command = None;
+            }
+            continue;
+        }
+        let Some(c) = command else {
+            return Err(SvgPathError::MalformedData);
+        };
+        let (kind, relative) = (c.to_ascii_uppercase(), c.is_ascii_lowercase());
+        let origin = if relative { current } else { Vec2::ZERO };
+        let arity = match kind {
+            'M' | 'L' => 2,
+            'H' | 'V' => 1,
+            'C' => 6,
+            _ => return Err(SvgPathError::UnsupportedCommand(c)),
+        };
+        let numbers = tokens
+            .get(i..i + arity)
+            .and_then(|group| {
+                group
+                    .iter()
+                    .map(|token| match token {
+                        Token::Number(number) => Some(*number),
+                        Token::Command(_) => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or(SvgPathError::MalformedData)?;
+        i += arity;
+        // Everything but a move carries on from a previous point
+        if kind != 'M' && points.is_empty() {
+            return Err(SvgPathError::MalformedData);
+        }
+        let point = |j: usize| origin + Vec2::new(numbers[j], numbers[j + 1]);
+        let end = match kind {
+            'M' => {
+                if !points.is_empty() {
+                    return Err(SvgPathError::MultipleSubpaths);
+                }
+                points.push(point(0));
+                subpath_start = point(0);
+                // Pairs after a move are implicit lines
+                command = Some(if relative { 'l' } else { 'L' });
+                current = point(0);
+                continue;
+            }
+            'C' => {
+                points.extend([point(0), point(2), point(4)]);
+                current = point(4);
+                continue;
+            }
+            'L' => point(0),
+            'H' => Vec2::new(origin.x + numbers[0], current.y),
+            'V' => Vec2::new(current.x, origin.y + numbers[0]),
+            _ => unreachable!(),
+        };
+        points.extend(straight_cubic(current, end));
+        linearized = true;
+        current = end;
+    }
+    if linearized {
+        warn!("SVG path has straight segments, which were imported as straight cubics");
+    }
+    Ok(points)
+}
+
+/// Handles and end point of a cubic running straight from `start` to `end` at an even pace
+fn straight_cubic(start: Vec2, end: Vec2) -> [Vec2; 3] {
+    [start.lerp(end, 1.0 / 3.0), start.lerp(end, 2.0 / 3.0), end]
+}